@@ -42,7 +42,7 @@ async fn inner() -> Result<(), Error> {
     assert_eq!(&index_retrieved[0].1, &value);
 
     // the macro also generates a get method for each index
-    let index_retrieved = Entry::get_by_name("One").await?;
+    let index_retrieved = Entry::get_by_name("One".to_string()).await?;
     assert_eq!(index_retrieved[0].0, 1);
     assert_eq!(&index_retrieved[0].1, &value);
 
@@ -53,7 +53,7 @@ async fn inner() -> Result<(), Error> {
         name: "One".to_string(),
     };
     value2.save().await?;
-    let index_retrieved = Entry::get_by_name("One").await?;
+    let index_retrieved = Entry::get_by_name("One".to_string()).await?;
     assert_eq!(index_retrieved.len(), 2);
     assert_eq!(index_retrieved[0].0, 1);
     assert_eq!(&index_retrieved[0].1, &value);
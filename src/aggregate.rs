@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+use crate::backend::{Entry, FoldFn, FoldState};
+use crate::{AsKey, Error, Key, MaybeSend, StoreRef, UniIndex, UniTable, Value, decode_raw, key};
+
+impl<K: Key, V: Value, S: StoreRef> UniTable<'_, K, V, S> {
+    /// Folds all entries with keys inside `range` into a single value.
+    /// The backend runs the fold where it reads the entries, on the worker thread of the native
+    /// backends, so the values are decoded and folded there and only the result is sent back.
+    pub async fn fold<A, F>(
+        &self,
+        range: impl RangeBounds<K>,
+        init: A,
+        mut f: F,
+    ) -> Result<A, Error>
+    where
+        K: 'static,
+        V: 'static,
+        A: MaybeSend + 'static,
+        F: FnMut(A, K, V) -> A + MaybeSend + 'static,
+    {
+        let range = self.encode_range(range);
+        let string_keys = self.backend().string_keys();
+        let step: FoldFn = Box::new(move |acc: FoldState, (key, value): Entry| {
+            let acc = *acc
+                .downcast::<A>()
+                .expect("The fold state should keep the type of init");
+            let key = key::decode_key(&key, string_keys)?;
+            Ok(Box::new(f(acc, key, decode_raw(value)?)))
+        });
+        let acc = self
+            .backend()
+            .fold_raw(&self.name, range, Box::new(init), step)
+            .await?;
+        Ok(*acc
+            .downcast::<A>()
+            .expect("The fold state should keep the type of init"))
+    }
+
+    /// Folds the entries with the given keys, missing keys are skipped.
+    pub(crate) async fn fold_keys<A, F>(&self, keys: Vec<K>, init: A, mut f: F) -> Result<A, Error>
+    where
+        F: FnMut(A, K, V) -> A,
+    {
+        let encoded = keys
+            .iter()
+            .map(|key| self.encode_key(key.clone()))
+            .collect();
+        let values = self.backend().get_many(&self.name, encoded).await?;
+        keys.into_iter()
+            .zip(values)
+            .try_fold(init, |acc, (key, value)| match value {
+                Some(value) => Ok(f(acc, key, decode_raw(value)?)),
+                None => Ok(acc),
+            })
+    }
+
+    /// Counts the values for which `predicate` returns `true`.
+    pub async fn count(
+        &self,
+        predicate: impl Fn(&V) -> bool + MaybeSend + 'static,
+    ) -> Result<usize, Error>
+    where
+        K: 'static,
+        V: 'static,
+    {
+        self.fold(
+            ..,
+            0,
+            move |count, _, value| {
+                if predicate(&value) { count + 1 } else { count }
+            },
+        )
+        .await
+    }
+
+    /// Groups all values by the result of `group` and folds each group separately.
+    pub async fn group_by<G, A, F>(
+        &self,
+        group: impl Fn(&V) -> G + MaybeSend + 'static,
+        init: A,
+        mut f: F,
+    ) -> Result<BTreeMap<G, A>, Error>
+    where
+        K: 'static,
+        V: 'static,
+        G: Ord + MaybeSend + 'static,
+        A: Clone + MaybeSend + 'static,
+        F: FnMut(A, K, V) -> A + MaybeSend + 'static,
+    {
+        self.fold(.., BTreeMap::new(), move |mut groups, key, value| {
+            let group = group(&value);
+            let acc = groups.remove(&group).unwrap_or_else(|| init.clone());
+            groups.insert(group, f(acc, key, value));
+            groups
+        })
+        .await
+    }
+
+    /// Returns the smallest key in the table without reading any value.
+    pub async fn min_key(&self) -> Result<Option<K>, Error> {
+        self.edge_key(false).await
+    }
+
+    /// Returns the largest key in the table without reading any value.
+    pub async fn max_key(&self) -> Result<Option<K>, Error> {
+        self.edge_key(true).await
+    }
+
+    async fn edge_key(&self, last: bool) -> Result<Option<K>, Error> {
+        let keys = self
+            .backend()
+            .scan_keys(
+                &self.name,
+                (Bound::Unbounded, Bound::Unbounded),
+                last,
+                Some(1),
+            )
+            .await?;
+        let key = keys.into_iter().next();
+        key.map(|key| self.decode_key(&key)).transpose()
+    }
+}
+
+impl<I: Key, K: Key, V: Value, S: StoreRef> UniIndex<'_, I, K, V, S> {
+    /// Folds the items whose indexed value matches `value`.
+    pub async fn fold<A, F>(&self, value: impl AsKey<I>, init: A, f: F) -> Result<A, Error>
+    where
+        F: FnMut(A, K, V) -> A,
+    {
+        let keys = self.get_keys(value).await?;
+        self.table.fold_keys(keys, init, f).await
+    }
+
+    /// Groups the keys in the index by their indexed value and folds each group.
+    /// Only the index is read, the values in the base table are not loaded.
+    pub async fn group_by<A, F>(&self, init: A, mut f: F) -> Result<BTreeMap<String, A>, Error>
+    where
+        K: 'static,
+        A: Clone + MaybeSend + 'static,
+        F: FnMut(A, K) -> A + MaybeSend + 'static,
+    {
+        self.index
+            .fold(.., Ok(BTreeMap::new()), move |groups, entry, ()| {
+                let mut groups: BTreeMap<String, A> = groups?;
+                let (value, key) = entry
+                    .split_once('\0')
+                    .expect("Index key should contain a separator");
+                let key = K::from_key_string(key)?;
+                let acc = groups.remove(value).unwrap_or_else(|| init.clone());
+                groups.insert(value.to_string(), f(acc, key));
+                Ok(groups)
+            })
+            .await?
+    }
+
+    /// Returns the smallest indexed value.
+    pub async fn min_key(&self) -> Result<Option<String>, Error> {
+        let entry = self.index.min_key().await?;
+        Ok(entry.map(Self::indexed_value))
+    }
+
+    /// Returns the largest indexed value.
+    pub async fn max_key(&self) -> Result<Option<String>, Error> {
+        let entry = self.index.max_key().await?;
+        Ok(entry.map(Self::indexed_value))
+    }
+
+    fn indexed_value(entry: String) -> String {
+        let (value, _) = entry
+            .split_once('\0')
+            .expect("Index key should contain a separator");
+        value.to_string()
+    }
+}
@@ -0,0 +1,253 @@
+use std::{any::Any, ops::Bound};
+
+use crate::{Error, MaybeSend, MaybeSync, RawValue};
+
+/// Future returned by [`Backend`] methods, `Send` on native targets.
+#[cfg(not(target_arch = "wasm32"))]
+pub type BackendFuture<'a, T> = futures::future::BoxFuture<'a, Result<T, Error>>;
+/// Future returned by [`Backend`] methods, `Send` on native targets.
+#[cfg(target_arch = "wasm32")]
+pub type BackendFuture<'a, T> = futures::future::LocalBoxFuture<'a, Result<T, Error>>;
+
+/// Lower and upper bound of a key range in encoded form.
+pub type KeyBounds = (Bound<Vec<u8>>, Bound<Vec<u8>>);
+
+/// An encoded key and its value in stored representation.
+pub type Entry = (Vec<u8>, RawValue);
+
+/// Accumulator of [`Backend::fold_raw`], `Send` on native targets.
+#[cfg(not(target_arch = "wasm32"))]
+pub type FoldState = Box<dyn Any + Send>;
+/// Accumulator of [`Backend::fold_raw`], `Send` on native targets.
+#[cfg(target_arch = "wasm32")]
+pub type FoldState = Box<dyn Any>;
+
+/// Step of [`Backend::fold_raw`], called with the accumulator and each entry in key order.
+#[cfg(not(target_arch = "wasm32"))]
+pub type FoldFn = Box<dyn FnMut(FoldState, Entry) -> Result<FoldState, Error> + Send>;
+/// Step of [`Backend::fold_raw`], called with the accumulator and each entry in key order.
+#[cfg(target_arch = "wasm32")]
+pub type FoldFn = Box<dyn FnMut(FoldState, Entry) -> Result<FoldState, Error>>;
+
+/// A write that is applied as part of an atomic batch, see [`Backend::write`].
+#[derive(Debug, Clone)]
+pub enum WriteOp {
+    Insert {
+        table: String,
+        key: Vec<u8>,
+        value: RawValue,
+    },
+    Remove {
+        table: String,
+        key: Vec<u8>,
+    },
+}
+
+/// Storage engine behind a [`UniStore`](crate::UniStore).
+///
+/// Backends work on encoded keys and values. Keys are ordered by their bytes,
+/// values are kept in the [`RawValue`] representation of the target.
+/// fjall (native), IndexedDB (wasm) and [`MemoryBackend`](crate::MemoryBackend) are built in,
+/// other backends are used with [`UniStore::with_backend`](crate::UniStore::with_backend).
+///
+/// Indexes and full-text indexes are stored in regular tables, so a backend only has to provide
+/// ordered key-value tables. Tables are created with [`Backend::open_table`] before they are used.
+/// Other methods never create tables: on a missing table reads return nothing, removals do nothing
+/// and inserts fail with [`Error::TableNotFound`].
+/// Errors that don't fit another variant can be returned as [`Error::Backend`].
+pub trait Backend: MaybeSend + MaybeSync {
+    /// Whether keys are encoded with [`Key::to_key_string`](crate::Key::to_key_string)
+    /// instead of [`Key::as_bytes`](crate::Key::as_bytes).
+    /// Defaults to string keys on wasm, matching IndexedDB.
+    fn string_keys(&self) -> bool {
+        cfg!(target_arch = "wasm32")
+    }
+
+    /// Whether structs in values are encoded as maps with their field names instead of arrays,
+    /// for backends that present the stored values to people. Only used natively.
+    fn named_values(&self) -> bool {
+        false
+    }
+
+    /// Waits for pending writes and releases the storage, see [`UniStore::close`](crate::UniStore::close).
+    /// Later operations fail. Does nothing by default.
+    fn close(&self) {}
+
+    /// Creates the table if it does not exist, returns `true` if it was created.
+    fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool>;
+
+    /// Deletes the table and all its entries.
+    fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()>;
+
+    fn list_tables(&self) -> BackendFuture<'_, Vec<String>>;
+
+    fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>>;
+
+    fn get_many<'a>(
+        &'a self,
+        table: &'a str,
+        keys: Vec<Vec<u8>>,
+    ) -> BackendFuture<'a, Vec<Option<RawValue>>> {
+        Box::pin(async move {
+            let mut values = Vec::with_capacity(keys.len());
+            for key in keys {
+                values.push(self.get(table, key).await?);
+            }
+            Ok(values)
+        })
+    }
+
+    fn insert<'a>(&'a self, table: &'a str, key: Vec<u8>, value: RawValue)
+    -> BackendFuture<'a, ()>;
+
+    fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()>;
+
+    fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize>;
+
+    /// Returns the entries inside `range` ordered by key, or in reverse order if `reverse` is set.
+    fn scan<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Entry>>;
+
+    /// Like [`Backend::scan`], but only returns the keys.
+    /// The default implementation reads the values as well and drops them.
+    fn scan_keys<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Vec<u8>>> {
+        Box::pin(async move {
+            let entries = self.scan(table, range, reverse, limit).await?;
+            Ok(entries.into_iter().map(|(key, _)| key).collect())
+        })
+    }
+
+    /// Folds the entries inside `range` in key order, starting with `init`.
+    ///
+    /// Backends should run `f` where they read the entries, like the built-in native backends
+    /// do on their worker thread, so the entries are never collected. The default implementation
+    /// reads them with [`Backend::scan`] and folds them afterwards.
+    fn fold_raw<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        init: FoldState,
+        mut f: FoldFn,
+    ) -> BackendFuture<'a, FoldState> {
+        Box::pin(async move {
+            let entries = self.scan(table, range, false, None).await?;
+            fold_entries(entries.into_iter().map(Ok), init, &mut f)
+        })
+    }
+
+    /// Atomically removes and returns the first or last entry.
+    fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>>;
+
+    /// Applies all writes atomically, used for [`Transaction`](crate::Transaction)s
+    /// and for removing entries together with their index entries.
+    fn write(&self, batch: Vec<WriteOp>) -> BackendFuture<'_, ()>;
+
+    /// Renames each table of `renames` from its old to its new name, which does not exist yet,
+    /// and applies `batch` together with the renames.
+    ///
+    /// The default implementation creates the new tables and copies the entries in the same
+    /// [`Backend::write`] as `batch`. The old tables are deleted afterwards; failing to delete
+    /// one is only logged, as the renamed tables are complete at that point.
+    fn rename_tables(
+        &self,
+        renames: Vec<(String, String)>,
+        mut batch: Vec<WriteOp>,
+    ) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            for (old, new) in &renames {
+                self.open_table(new).await?;
+                let entries = self
+                    .scan(old, (Bound::Unbounded, Bound::Unbounded), false, None)
+                    .await?;
+                batch.extend(entries.into_iter().map(|(key, value)| WriteOp::Insert {
+                    table: new.clone(),
+                    key,
+                    value,
+                }));
+            }
+            if let Err(e) = self.write(batch).await {
+                for (_, new) in &renames {
+                    let _ = self.delete_table(new).await;
+                }
+                return Err(e);
+            }
+            for (old, _) in &renames {
+                if let Err(e) = self.delete_table(old).await {
+                    tracing::warn!("Failed to delete table {old} after renaming it: {e}");
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Folds `entries` with the step of [`Backend::fold_raw`], stopping at the first error.
+pub(crate) fn fold_entries(
+    entries: impl IntoIterator<Item = Result<Entry, Error>>,
+    init: FoldState,
+    f: &mut FoldFn,
+) -> Result<FoldState, Error> {
+    entries
+        .into_iter()
+        .try_fold(init, |acc, entry| f(acc, entry?))
+}
+
+/// Converts a key prefix into the range of keys starting with it.
+/// The upper bound of a UTF-8 prefix is valid UTF-8 as well, so it can be used with string keys.
+pub(crate) fn prefix_range(mut prefix: Vec<u8>) -> KeyBounds {
+    let start = Bound::Included(prefix.clone());
+    if let Ok(prefix) = std::str::from_utf8(&prefix) {
+        let mut chars = prefix.chars().collect::<Vec<_>>();
+        while let Some(last) = chars.pop() {
+            if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+                chars.push(next);
+                let end = chars.into_iter().collect::<String>().into_bytes();
+                return (start, Bound::Excluded(end));
+            }
+        }
+        return (start, Bound::Unbounded);
+    }
+    while let Some(last) = prefix.pop() {
+        if last < u8::MAX {
+            prefix.push(last + 1);
+            return (start, Bound::Excluded(prefix));
+        }
+    }
+    (start, Bound::Unbounded)
+}
+
+/// Returns whether `range` can't contain any key because its start lies behind its end.
+pub(crate) fn is_empty_range(range: &KeyBounds) -> bool {
+    match range {
+        (Bound::Included(start) | Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+        (Bound::Excluded(start), Bound::Included(end)) => start >= end,
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        _ => false,
+    }
+}
+
+/// Returns whether `key` lies inside `range`.
+pub(crate) fn in_range(range: &KeyBounds, key: &[u8]) -> bool {
+    let lower = match &range.0 {
+        Bound::Included(bound) => key >= bound.as_slice(),
+        Bound::Excluded(bound) => key > bound.as_slice(),
+        Bound::Unbounded => true,
+    };
+    let upper = match &range.1 {
+        Bound::Included(bound) => key <= bound.as_slice(),
+        Bound::Excluded(bound) => key < bound.as_slice(),
+        Bound::Unbounded => true,
+    };
+    lower && upper
+}
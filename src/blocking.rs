@@ -0,0 +1,438 @@
+use std::ops::RangeBounds;
+
+use futures::executor::block_on;
+
+use crate::{
+    AsKey, AsValue, Error, ItemQuery, Key, MaybeSend, Query, Transaction, UniFullTextIndex,
+    UniIndex, UniStore, UniStoreBuilder, UniStoreItem, UniTable, Value,
+};
+
+/// A [`UniStore`] with synchronous methods for code that cannot be async, like CLI tools and
+/// `Drop` impls (native only, requires the `blocking` feature).
+///
+/// The native backends run on their own worker thread, so no async runtime is needed.
+/// Calling the methods from async code blocks the executor thread while waiting.
+///
+/// Tables and their indexes are wrapped in [`BlockingUniTable`], [`BlockingUniIndex`] and
+/// [`BlockingUniFullTextIndex`]. Queries and transactions are built with the regular API and run
+/// with [`Query::execute_blocking`] and [`Transaction::commit_blocking`], derived items use
+/// [`BlockingUniStoreItem`].
+#[derive(Debug)]
+pub struct BlockingUniStore {
+    store: UniStore,
+}
+
+/// A [`UniTable`] with synchronous methods, created by [`BlockingUniStore::create_table`].
+#[derive(Debug)]
+pub struct BlockingUniTable<'a, K: Key, V: Value> {
+    table: UniTable<'a, K, V>,
+}
+
+/// A [`UniIndex`] with synchronous methods, created by [`BlockingUniTable::create_index`].
+#[derive(Debug)]
+pub struct BlockingUniIndex<'a, I: Key, K: Key, V: Value> {
+    index: UniIndex<'a, I, K, V>,
+}
+
+/// A [`UniFullTextIndex`] with synchronous methods,
+/// created by [`BlockingUniTable::create_fulltext_index`].
+#[derive(Debug)]
+pub struct BlockingUniFullTextIndex<'a, K: Key, V: Value> {
+    index: UniFullTextIndex<'a, K, V>,
+}
+
+impl BlockingUniStore {
+    /// Opens the store in the data directory of the given application.
+    pub fn new(qualifier: &str, organization: &str, application: &str) -> Result<Self, Error> {
+        Self::build(UniStore::builder().app(qualifier, organization, application))
+    }
+
+    /// Opens the store configured in `builder`.
+    pub fn build(builder: UniStoreBuilder) -> Result<Self, Error> {
+        Ok(block_on(builder.build())?.into())
+    }
+
+    /// Opens a store that keeps all data in memory.
+    pub fn in_memory() -> Self {
+        UniStore::in_memory().into()
+    }
+
+    /// Opens a store in a fresh temporary directory that is removed when the store is dropped.
+    pub fn temporary() -> Result<Self, Error> {
+        Ok(block_on(UniStore::temporary())?.into())
+    }
+
+    /// Returns the async store.
+    pub fn store(&self) -> &UniStore {
+        &self.store
+    }
+
+    pub fn into_inner(self) -> UniStore {
+        self.store
+    }
+
+    /// See [`UniStore::close`].
+    pub fn close(&self) {
+        self.store.close();
+    }
+
+    /// See [`UniStore::create_table`].
+    pub fn create_table<K: Key, V: Value>(
+        &self,
+        name: &str,
+        replace_if_incompatible: bool,
+    ) -> Result<BlockingUniTable<'_, K, V>, Error> {
+        let table = block_on(self.store.create_table(name, replace_if_incompatible))?;
+        Ok(table.into())
+    }
+
+    pub fn list_tables(&self) -> Result<Vec<String>, Error> {
+        block_on(self.store.list_tables())
+    }
+
+    pub fn drop_table(&self, name: &str) -> Result<(), Error> {
+        block_on(self.store.drop_table(name))
+    }
+
+    pub fn rename_table(&self, old: &str, new: &str) -> Result<(), Error> {
+        block_on(self.store.rename_table(old, new))
+    }
+
+    /// See [`UniStore::transaction`], commit it with [`Transaction::commit_blocking`].
+    pub fn transaction(&self) -> Transaction<'_> {
+        self.store.transaction()
+    }
+}
+
+impl From<UniStore> for BlockingUniStore {
+    fn from(store: UniStore) -> Self {
+        BlockingUniStore { store }
+    }
+}
+
+impl<'a, K: Key, V: Value> BlockingUniTable<'a, K, V> {
+    /// Returns the async table, e.g. for indexes and queries.
+    pub fn table(&self) -> &UniTable<'a, K, V> {
+        &self.table
+    }
+
+    pub fn into_inner(self) -> UniTable<'a, K, V> {
+        self.table
+    }
+
+    pub fn insert(&self, key: impl AsKey<K>, value: impl AsValue<V>) -> Result<(), Error> {
+        block_on(self.table.insert(key, value))
+    }
+
+    pub fn contains(&self, key: impl AsKey<K>) -> Result<bool, Error> {
+        block_on(self.table.contains(key))
+    }
+
+    pub fn get(&self, key: impl AsKey<K>) -> Result<Option<V>, Error> {
+        block_on(self.table.get(key))
+    }
+
+    /// See [`UniTable::get_many`].
+    pub fn get_many(
+        &self,
+        keys: impl IntoIterator<Item = impl AsKey<K>>,
+    ) -> Result<Vec<Option<V>>, Error> {
+        block_on(self.table.get_many(keys))
+    }
+
+    pub fn remove(&self, key: impl AsKey<K>) -> Result<(), Error> {
+        block_on(self.table.remove(key))
+    }
+
+    pub fn len(&self) -> Result<usize, Error> {
+        block_on(self.table.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        block_on(self.table.is_empty())
+    }
+
+    pub fn get_prefix(&self, prefix: impl AsKey<K>) -> Result<Vec<(K, V)>, Error> {
+        block_on(self.table.get_prefix(prefix))
+    }
+
+    pub fn get_range(&self, range: impl RangeBounds<K>) -> Result<Vec<(K, V)>, Error> {
+        block_on(self.table.get_range(range))
+    }
+
+    pub fn keys(&self) -> Result<Vec<K>, Error> {
+        block_on(self.table.keys())
+    }
+
+    pub fn keys_prefix(&self, prefix: impl AsKey<K>) -> Result<Vec<K>, Error> {
+        block_on(self.table.keys_prefix(prefix))
+    }
+
+    pub fn keys_range(&self, range: impl RangeBounds<K>) -> Result<Vec<K>, Error> {
+        block_on(self.table.keys_range(range))
+    }
+
+    /// See [`UniTable::clear`].
+    pub fn clear(&self) -> Result<usize, Error> {
+        block_on(self.table.clear())
+    }
+
+    /// See [`UniTable::remove_prefix`].
+    pub fn remove_prefix(&self, prefix: impl AsKey<K>) -> Result<usize, Error> {
+        block_on(self.table.remove_prefix(prefix))
+    }
+
+    /// See [`UniTable::remove_range`].
+    pub fn remove_range(&self, range: impl RangeBounds<K>) -> Result<usize, Error> {
+        block_on(self.table.remove_range(range))
+    }
+
+    pub fn first(&self) -> Result<Option<(K, V)>, Error> {
+        block_on(self.table.first())
+    }
+
+    pub fn last(&self) -> Result<Option<(K, V)>, Error> {
+        block_on(self.table.last())
+    }
+
+    pub fn pop_first(&self) -> Result<Option<(K, V)>, Error> {
+        block_on(self.table.pop_first())
+    }
+
+    pub fn pop_last(&self) -> Result<Option<(K, V)>, Error> {
+        block_on(self.table.pop_last())
+    }
+
+    /// See [`UniTable::query`], run it with [`Query::execute_blocking`].
+    pub fn query(&self) -> Query<'_, K, V> {
+        self.table.query()
+    }
+
+    /// See [`UniTable::create_index`].
+    pub fn create_index<I: Key>(
+        &self,
+        index: &'static str,
+    ) -> Result<BlockingUniIndex<'a, I, K, V>, Error> {
+        Ok(block_on(self.table.create_index(index))?.into())
+    }
+
+    /// See [`UniTable::create_index_normalized`].
+    pub fn create_index_normalized<I: Key>(
+        &self,
+        index: &'static str,
+        normalize: fn(&str) -> String,
+    ) -> Result<BlockingUniIndex<'a, I, K, V>, Error> {
+        Ok(block_on(self.table.create_index_normalized(index, normalize))?.into())
+    }
+
+    /// See [`UniTable::create_fulltext_index`].
+    pub fn create_fulltext_index(
+        &self,
+        index: &'static str,
+    ) -> Result<BlockingUniFullTextIndex<'a, K, V>, Error> {
+        Ok(block_on(self.table.create_fulltext_index(index))?.into())
+    }
+
+    /// See [`UniTable::fold`].
+    pub fn fold<A, F>(&self, range: impl RangeBounds<K>, init: A, f: F) -> Result<A, Error>
+    where
+        K: 'static,
+        V: 'static,
+        A: MaybeSend + 'static,
+        F: FnMut(A, K, V) -> A + MaybeSend + 'static,
+    {
+        block_on(self.table.fold(range, init, f))
+    }
+
+    /// See [`UniTable::count`].
+    pub fn count(
+        &self,
+        predicate: impl Fn(&V) -> bool + MaybeSend + 'static,
+    ) -> Result<usize, Error>
+    where
+        K: 'static,
+        V: 'static,
+    {
+        block_on(self.table.count(predicate))
+    }
+
+    /// See [`UniTable::min_key`].
+    pub fn min_key(&self) -> Result<Option<K>, Error> {
+        block_on(self.table.min_key())
+    }
+
+    /// See [`UniTable::max_key`].
+    pub fn max_key(&self) -> Result<Option<K>, Error> {
+        block_on(self.table.max_key())
+    }
+}
+
+impl<'a, K: Key, V: Value> From<UniTable<'a, K, V>> for BlockingUniTable<'a, K, V> {
+    fn from(table: UniTable<'a, K, V>) -> Self {
+        BlockingUniTable { table }
+    }
+}
+
+impl<'a, I: Key, K: Key + Clone, V: Value> BlockingUniIndex<'a, I, K, V> {
+    /// Returns the async index.
+    pub fn index(&self) -> &UniIndex<'a, I, K, V> {
+        &self.index
+    }
+
+    pub fn into_inner(self) -> UniIndex<'a, I, K, V> {
+        self.index
+    }
+
+    /// See [`UniIndex::get_keys`].
+    pub fn get_keys(&self, value: impl AsKey<I>) -> Result<Vec<K>, Error> {
+        block_on(self.index.get_keys(value))
+    }
+
+    pub fn get(&self, value: impl AsKey<I>) -> Result<Vec<(K, V)>, Error> {
+        block_on(self.index.get(value))
+    }
+
+    /// See [`UniIndex::get_many`].
+    pub fn get_many(
+        &self,
+        values: impl IntoIterator<Item = impl AsKey<I>>,
+    ) -> Result<Vec<Vec<(K, V)>>, Error> {
+        block_on(self.index.get_many(values))
+    }
+
+    pub fn get_first(&self, value: impl AsKey<I>) -> Result<Option<(K, V)>, Error> {
+        block_on(self.index.get_first(value))
+    }
+
+    pub fn insert(&self, value: impl AsKey<I>, key: impl AsKey<K>) -> Result<(), Error> {
+        block_on(self.index.insert(value, key))
+    }
+
+    /// See [`UniIndex::count`].
+    pub fn count(&self, value: impl AsKey<I>) -> Result<usize, Error> {
+        block_on(self.index.count(value))
+    }
+
+    /// See [`UniIndex::remove`].
+    pub fn remove(&self, key: impl AsKey<K>) -> Result<(), Error> {
+        block_on(self.index.remove(key))
+    }
+
+    /// See [`UniIndex::remove_all`].
+    pub fn remove_all(&self, value: impl AsKey<I>) -> Result<usize, Error> {
+        block_on(self.index.remove_all(value))
+    }
+}
+
+impl<'a, I: Key, K: Key, V: Value> From<UniIndex<'a, I, K, V>> for BlockingUniIndex<'a, I, K, V> {
+    fn from(index: UniIndex<'a, I, K, V>) -> Self {
+        BlockingUniIndex { index }
+    }
+}
+
+impl<'a, K: Key, V: Value> BlockingUniFullTextIndex<'a, K, V> {
+    /// Returns the async index.
+    pub fn index(&self) -> &UniFullTextIndex<'a, K, V> {
+        &self.index
+    }
+
+    pub fn into_inner(self) -> UniFullTextIndex<'a, K, V> {
+        self.index
+    }
+
+    /// See [`UniFullTextIndex::insert`].
+    pub fn insert(&self, text: &str, key: impl AsKey<K>) -> Result<(), Error> {
+        block_on(self.index.insert(text, key))
+    }
+
+    /// See [`UniFullTextIndex::remove`].
+    pub fn remove(&self, key: impl AsKey<K>) -> Result<(), Error> {
+        block_on(self.index.remove(key))
+    }
+
+    /// See [`UniFullTextIndex::search`].
+    pub fn search(&self, query: &str) -> Result<Vec<(K, V)>, Error> {
+        block_on(self.index.search(query))
+    }
+}
+
+impl<'a, K: Key, V: Value> From<UniFullTextIndex<'a, K, V>> for BlockingUniFullTextIndex<'a, K, V> {
+    fn from(index: UniFullTextIndex<'a, K, V>) -> Self {
+        BlockingUniFullTextIndex { index }
+    }
+}
+
+impl<K: Key, V: Value> Query<'_, K, V> {
+    /// Runs the query like [`Query::execute`], blocking until it is done.
+    pub fn execute_blocking(self) -> Result<Vec<(K, V)>, Error> {
+        block_on(self.execute())
+    }
+}
+
+impl<T: UniStoreItem> ItemQuery<T> {
+    /// Runs the query like [`ItemQuery::execute`], blocking until it is done.
+    pub fn execute_blocking(self) -> Result<Vec<(T::Key, T)>, Error> {
+        block_on(self.execute())
+    }
+}
+
+impl Transaction<'_> {
+    /// Applies all writes like [`Transaction::commit`], blocking until they are written.
+    pub fn commit_blocking(self) -> Result<(), Error> {
+        block_on(self.commit())
+    }
+}
+
+/// Synchronous versions of the [`UniStoreItem`] methods, implemented for all derived items.
+pub trait BlockingUniStoreItem: UniStoreItem {
+    /// See [`UniStoreItem::get`].
+    fn get_blocking(key: impl AsKey<Self::Key>) -> Result<Option<Self>, Error> {
+        block_on(Self::get(key))
+    }
+
+    /// See [`UniStoreItem::contains`].
+    fn contains_blocking(key: impl AsKey<Self::Key>) -> Result<bool, Error> {
+        block_on(Self::contains(key))
+    }
+
+    /// See [`UniStoreItem::save`].
+    fn save_blocking(&self) -> Result<(), Error> {
+        block_on(self.save())
+    }
+
+    /// See [`UniStoreItem::remove`].
+    fn remove_blocking(key: impl AsKey<Self::Key>) -> Result<(), Error> {
+        block_on(Self::remove(key))
+    }
+
+    /// See [`UniStoreItem::get_by_index`].
+    fn get_by_index_blocking<I: Key>(
+        index: &'static str,
+        key: impl AsKey<I>,
+    ) -> Result<Vec<(Self::Key, Self)>, Error> {
+        block_on(Self::get_by_index(index, key))
+    }
+
+    /// See [`UniStoreItem::count_by_index`].
+    fn count_by_index_blocking(
+        index: &'static str,
+        key: impl AsKey<String>,
+    ) -> Result<usize, Error> {
+        block_on(Self::count_by_index(index, key))
+    }
+
+    /// See [`UniStoreItem::remove_by_index`].
+    fn remove_by_index_blocking(
+        index: &'static str,
+        key: impl AsKey<String>,
+    ) -> Result<usize, Error> {
+        block_on(Self::remove_by_index(index, key))
+    }
+
+    /// See [`UniStoreItem::search`].
+    fn search_blocking(index: &'static str, query: &str) -> Result<Vec<(Self::Key, Self)>, Error> {
+        block_on(Self::search(index, query))
+    }
+}
+
+impl<T: UniStoreItem> BlockingUniStoreItem for T {}
@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+
+use crate::{Error, Mutex, UniStore, backend::Backend, memory::MemoryBackend};
+
+/// Builder for a [`UniStore`] with an explicit location and backend options,
+/// created by [`UniStore::builder`].
+///
+/// The location is either the data directory of an application, set with [`Self::app`],
+/// or an explicit directory set with [`Self::path`].
+/// The default engine is fjall natively and IndexedDB on wasm, other engines are selected with
+/// [`Self::in_memory`] and the methods of the optional engine features.
+/// Options that don't apply to the selected engine are ignored.
+#[derive(Debug, Clone)]
+pub struct UniStoreBuilder {
+    pub(crate) app: Option<(String, String, String)>,
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) file_name: Option<String>,
+    pub(crate) cache_size: Option<u64>,
+    pub(crate) write_buffer_size: Option<u64>,
+    pub(crate) fsync_ms: Option<Option<u16>>,
+    pub(crate) channel_capacity: usize,
+    pub(crate) engine: Engine,
+    pub(crate) temporary: bool,
+}
+
+/// Storage engine of the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Engine {
+    /// fjall natively, IndexedDB on wasm
+    Default,
+    Memory,
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    Sqlite,
+    #[cfg(all(feature = "redb", not(target_arch = "wasm32")))]
+    Redb,
+    #[cfg(all(feature = "json-file", not(target_arch = "wasm32")))]
+    JsonFile,
+}
+
+impl Default for UniStoreBuilder {
+    fn default() -> Self {
+        Self {
+            app: None,
+            path: None,
+            file_name: None,
+            cache_size: None,
+            write_buffer_size: None,
+            fsync_ms: None,
+            channel_capacity: 16,
+            engine: Engine::Default,
+            temporary: false,
+        }
+    }
+}
+
+impl UniStoreBuilder {
+    /// Stores the data in the data directory of the given application.
+    pub fn app(mut self, qualifier: &str, organization: &str, application: &str) -> Self {
+        self.app = Some((
+            qualifier.to_string(),
+            organization.to_string(),
+            application.to_string(),
+        ));
+        self
+    }
+
+    /// Stores the data in `path` instead of the application data directory.
+    /// Only used by the native backend.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Name of the database inside the data directory, `unistore.fjall` by default.
+    /// On wasm this is the name of the IndexedDB database,
+    /// which defaults to `qualifier.organization.application`.
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_string());
+        self
+    }
+
+    /// Size of the block cache in bytes (native only).
+    pub fn cache_size(mut self, bytes: u64) -> Self {
+        self.cache_size = Some(bytes);
+        self
+    }
+
+    /// Maximum size of all write buffers in bytes before they are flushed (native only).
+    pub fn write_buffer_size(mut self, bytes: u64) -> Self {
+        self.write_buffer_size = Some(bytes);
+        self
+    }
+
+    /// Interval in milliseconds in which the journal is synced to disk,
+    /// `None` leaves syncing to the operating system (native only).
+    pub fn fsync_ms(mut self, ms: Option<u16>) -> Self {
+        self.fsync_ms = Some(ms);
+        self
+    }
+
+    /// Number of requests that can be queued for the storage worker thread (native only).
+    pub fn channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+
+    /// Keeps all data in memory instead of persisting it, for tests and ephemeral stores.
+    /// No location is required, the data is lost when the store is dropped.
+    pub fn in_memory(mut self) -> Self {
+        self.engine = Engine::Memory;
+        self
+    }
+
+    /// Stores the data in a single SQLite file, with a table with BLOB keys and values for each
+    /// table of the store (native only, requires the `sqlite` feature).
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    pub fn sqlite(mut self) -> Self {
+        self.engine = Engine::Sqlite;
+        self
+    }
+
+    /// Stores the data in a fresh temporary directory that is removed when the store is dropped
+    /// or closed. On wasm the data is kept in memory instead.
+    pub fn temporary(mut self) -> Self {
+        self.temporary = true;
+        self
+    }
+
+    /// Stores the data in a single redb file, a B-tree without background threads
+    /// (native only, requires the `redb` feature).
+    #[cfg(all(feature = "redb", not(target_arch = "wasm32")))]
+    pub fn redb(mut self) -> Self {
+        self.engine = Engine::Redb;
+        self
+    }
+
+    /// Stores each table as a pretty-printed JSON file that can be read and edited by hand,
+    /// meant for small datasets, fixtures and debugging (native only, requires the `json-file`
+    /// feature). Keys are ordered as strings, see [`Key::to_key_string`](crate::Key::to_key_string).
+    #[cfg(all(feature = "json-file", not(target_arch = "wasm32")))]
+    pub fn json_file(mut self) -> Self {
+        self.engine = Engine::JsonFile;
+        self
+    }
+
+    /// Name of the store, used as database name on wasm.
+    fn name(&self) -> Result<String, Error> {
+        if let Some(file_name) = &self.file_name {
+            return Ok(file_name.clone());
+        }
+        if let Some((qualifier, organization, application)) = &self.app {
+            return Ok(format!("{qualifier}.{organization}.{application}"));
+        }
+        if let Some(path) = &self.path {
+            return Ok(path.to_string_lossy().to_string());
+        }
+        if self.engine == Engine::Memory {
+            return Ok("memory".to_string());
+        }
+        if self.temporary {
+            return Ok("temporary".to_string());
+        }
+        Err(Error::InvalidConfig(
+            "Either an application or a path is required".to_string(),
+        ))
+    }
+
+    /// Opens the store.
+    pub async fn build(self) -> Result<UniStore, Error> {
+        let name = self.name()?;
+        let backend: Box<dyn Backend> = match self.engine {
+            Engine::Memory => Box::new(MemoryBackend::default()),
+            Engine::Default if cfg!(target_arch = "wasm32") && self.temporary => {
+                Box::new(MemoryBackend::default())
+            }
+            #[cfg(target_arch = "wasm32")]
+            Engine::Default => Box::new(crate::wasm::IdbBackend::open(&name).await?),
+            #[cfg(not(target_arch = "wasm32"))]
+            Engine::Default => Box::new(crate::native::FjallBackend::open(&self).await?),
+            #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+            Engine::Sqlite => Box::new(crate::sqlite::SqliteBackend::open(&self).await?),
+            #[cfg(all(feature = "redb", not(target_arch = "wasm32")))]
+            Engine::Redb => Box::new(crate::redb::RedbBackend::open(&self).await?),
+            #[cfg(all(feature = "json-file", not(target_arch = "wasm32")))]
+            Engine::JsonFile => Box::new(crate::json_file::JsonFileBackend::open(&self).await?),
+        };
+        Ok(UniStore {
+            backend,
+            name,
+            popping: Mutex::new(()),
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Error, Key, UniStore, UniTable};
+
+/// Self-describing value of a dynamic table.
+///
+/// Natively the values are stored as msgpack, where structs are encoded as arrays,
+/// so a struct is read as a JSON array of its fields. On wasm and in backends with
+/// [named values](crate::Backend::named_values) it is read as an object.
+pub type DynValue = serde_json::Value;
+
+/// A table opened without knowing its key and value types, see [`UniStore::open_dyn_table`].
+pub type DynTable<'a> = UniTable<'a, DynKey, DynValue>;
+
+/// Untyped key of a dynamic table in the representation used by the backend:
+/// the encoded key bytes natively, the key string on wasm and in backends with string keys.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DynKey(pub Vec<u8>);
+
+impl DynKey {
+    /// Encodes a typed key like a [`UniTable`] with key type `K` stores it in the built-in
+    /// backend of the target. Use [`UniStore::dyn_key`] for other backends.
+    pub fn from_key<K: Key>(key: K) -> Self {
+        #[cfg(target_arch = "wasm32")]
+        let key = DynKey(key.to_key_string().into_bytes());
+        #[cfg(not(target_arch = "wasm32"))]
+        let key = DynKey(key.as_bytes());
+        key
+    }
+
+    /// Decodes the key into a typed key, see [`DynKey::from_key`] and [`UniStore::decode_dyn_key`].
+    pub fn decode<K: Key>(&self) -> Result<K, Error> {
+        #[cfg(target_arch = "wasm32")]
+        let key = K::from_key_string(&String::from_utf8_lossy(&self.0))?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let key = K::from_bytes(&self.0)?;
+        Ok(key)
+    }
+
+    /// Returns the key as a string if it is valid UTF-8, which is the case for string keys.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+}
+
+impl Key for DynKey {
+    fn as_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    fn to_key_string(self) -> String {
+        String::from_utf8_lossy(&self.0).into_owned()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(DynKey(bytes.to_vec()))
+    }
+
+    fn from_key_string(s: &str) -> Result<Self, Error> {
+        Ok(DynKey(s.as_bytes().to_vec()))
+    }
+}
+
+impl From<&str> for DynKey {
+    fn from(key: &str) -> Self {
+        DynKey(key.as_bytes().to_vec())
+    }
+}
+
+impl UniStore {
+    /// Opens an existing table without compile-time key and value types, e.g. for admin tools.
+    /// Fails with [`Error::TableNotFound`] if the table does not exist.
+    pub async fn open_dyn_table(&self, name: &str) -> Result<DynTable<'_>, Error> {
+        if !self.list_tables().await?.iter().any(|table| table == name) {
+            return Err(Error::TableNotFound(name.to_string()));
+        }
+        Ok(UniTable {
+            store: self,
+            name: name.to_string(),
+            phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Encodes a typed key into a [`DynKey`] in the representation used by the backend.
+    pub fn dyn_key<K: Key>(&self, key: K) -> DynKey {
+        DynKey(self.encode_key(key))
+    }
+
+    /// Decodes a [`DynKey`] read from this store into a typed key.
+    pub fn decode_dyn_key<K: Key>(&self, key: &DynKey) -> Result<K, Error> {
+        self.decode_key(&key.0)
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeSet;
+
+use crate::backend::WriteOp;
+use crate::{AsKey, Key, StoreRef, UniStore, UniTable, Value, normalize, raw};
+
+/// Splits a text into case folded words.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(normalize::case_fold)
+        .collect()
+}
+
+/// An inverted index over the words of a text field.
+///
+/// Every word is stored as `word\0key` in the index table, so a word lookup is a prefix scan.
+/// The reverse table keeps the words of each key to remove stale entries on updates.
+pub struct UniFullTextIndex<'a, K: Key, V: Value, S: StoreRef = &'a UniStore> {
+    pub table: UniTable<'a, K, V, S>,
+    pub index: UniTable<'a, String, (), S>,
+    pub index_rev: UniTable<'a, String, Vec<String>, S>,
+}
+
+impl<K: Key, V: Value, S: StoreRef> Clone for UniFullTextIndex<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        UniFullTextIndex {
+            table: self.table.clone(),
+            index: self.index.clone(),
+            index_rev: self.index_rev.clone(),
+        }
+    }
+}
+
+impl<K: Key, V: Value, S: StoreRef> std::fmt::Debug for UniFullTextIndex<'_, K, V, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UniFullTextIndex")
+            .field("table", &self.table.name)
+            .field("index", &self.index.name)
+            .finish()
+    }
+}
+
+impl<K: Key, V: Value, S: StoreRef> UniFullTextIndex<'_, K, V, S> {
+    /// Indexes `text` for `key`, replacing the words previously indexed for it.
+    /// All index entries of the key are written in a single batch.
+    pub async fn insert(&self, text: &str, key: impl AsKey<K>) -> Result<(), crate::Error> {
+        let key_str = key.as_key().to_key_string();
+        let words = tokenize(text).into_iter().collect::<BTreeSet<_>>();
+        let old_words = self.index_rev.get(key_str.as_str()).await?;
+        let named_values = self.table.backend().named_values();
+        let mut batch = Vec::new();
+        for word in old_words.unwrap_or_default() {
+            if !words.contains(&word) {
+                batch.push(WriteOp::Remove {
+                    table: self.index.name.clone(),
+                    key: self.index.encode_key(format!("{word}\0{key_str}")),
+                });
+            }
+        }
+        for word in &words {
+            batch.push(WriteOp::Insert {
+                table: self.index.name.clone(),
+                key: self.index.encode_key(format!("{word}\0{key_str}")),
+                value: raw::encode(&(), named_values)?,
+            });
+        }
+        batch.push(WriteOp::Insert {
+            table: self.index_rev.name.clone(),
+            key: self.index_rev.encode_key(key_str),
+            value: raw::encode(&words.into_iter().collect::<Vec<_>>(), named_values)?,
+        });
+        self.table.backend().write(batch).await
+    }
+
+    /// Removes all index entries for `key` in a single batch, leaving the base table untouched.
+    pub async fn remove(&self, key: impl AsKey<K>) -> Result<(), crate::Error> {
+        let key_str = key.as_key().to_key_string();
+        let Some(words) = self.index_rev.get(key_str.as_str()).await? else {
+            return Ok(());
+        };
+        let mut batch = words
+            .into_iter()
+            .map(|word| WriteOp::Remove {
+                table: self.index.name.clone(),
+                key: self.index.encode_key(format!("{word}\0{key_str}")),
+            })
+            .collect::<Vec<_>>();
+        batch.push(WriteOp::Remove {
+            table: self.index_rev.name.clone(),
+            key: self.index_rev.encode_key(key_str),
+        });
+        self.table.backend().write(batch).await
+    }
+
+    /// Returns the keys of all items containing `word`.
+    /// If `prefix` is set, all words starting with `word` match.
+    async fn matching_keys(
+        &self,
+        word: &str,
+        prefix: bool,
+    ) -> Result<BTreeSet<String>, crate::Error> {
+        let scan = if prefix {
+            word.to_string()
+        } else {
+            format!("{word}\0")
+        };
+        let entries = self.index.keys_prefix(scan).await?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let (_, key) = entry
+                    .split_once('\0')
+                    .expect("Index key should contain a separator");
+                key.to_string()
+            })
+            .collect())
+    }
+
+    /// Searches for items containing all words of `query`.
+    /// A word ending with `*` matches every word starting with it, e.g. `"rust prog*"`.
+    /// The words are looked up concurrently.
+    pub async fn search(&self, query: &str) -> Result<Vec<(K, V)>, crate::Error> {
+        let mut terms = Vec::new();
+        for word in query.split_whitespace() {
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(word) => (word, true),
+                None => (word, false),
+            };
+            let mut tokens = tokenize(word);
+            let last = tokens.pop();
+            terms.extend(tokens.into_iter().map(|token| (token, false)));
+            terms.extend(last.map(|token| (token, prefix)));
+        }
+        let lookups = terms
+            .iter()
+            .map(|(word, prefix)| self.matching_keys(word, *prefix));
+        let mut matches: Option<BTreeSet<String>> = None;
+        for keys in futures::future::try_join_all(lookups).await? {
+            let keys = match matches {
+                Some(matches) => matches.intersection(&keys).cloned().collect(),
+                None => keys,
+            };
+            if keys.is_empty() {
+                return Ok(Vec::new());
+            }
+            matches = Some(keys);
+        }
+        let keys = matches
+            .unwrap_or_default()
+            .iter()
+            .map(|key| K::from_key_string(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let values = self.table.get_many(keys.clone()).await?;
+        Ok(keys
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect())
+    }
+}
+
+impl<'a, K: Key, V: Value, S: StoreRef> UniTable<'a, K, V, S> {
+    pub async fn create_fulltext_index(
+        &self,
+        index: &'static str,
+    ) -> Result<UniFullTextIndex<'a, K, V, S>, crate::Error> {
+        let store = self.store.clone();
+        let index_table = UniTable::open(
+            store.clone(),
+            &format!("{}_fulltext_{index}", self.name),
+            false,
+        )
+        .await?;
+        let rev_index_table =
+            UniTable::open(store, &format!("{}_fulltext_{index}_rev", self.name), false).await?;
+        self.store
+            .register_index(&self.name, &index_table.name, true)
+            .await?;
+        Ok(UniFullTextIndex {
+            table: self.clone(),
+            index: index_table,
+            index_rev: rev_index_table,
+        })
+    }
+}
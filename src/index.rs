@@ -1,13 +1,53 @@
-use crate::{AsKey, Key, UniTable, Value};
+use crate::{AsKey, Error, Key, StoreRef, UniStore, UniTable, Value};
 
-pub struct UniIndex<'a, I: Key, K: Key, V: Value> {
-    pub table: &'a UniTable<'a, K, V>,
-    pub index: UniTable<'a, String, ()>,
-    pub index_rev: UniTable<'a, String, String>,
+/// Built-in normalizers that can be passed to [`UniTable::create_index_normalized`].
+pub mod normalize {
+    /// Lowercases the value, making the index case-insensitive.
+    pub fn lowercase(s: &str) -> String {
+        s.to_lowercase()
+    }
+
+    /// Uppercases and then lowercases the value, so characters whose uppercase form expands
+    /// match their expansion, e.g. `"Straße"` and `"STRASSE"` both become `"strasse"`
+    /// and `"ﬁ"` becomes `"fi"`.
+    ///
+    /// This is not full Unicode case folding: the capital sharp s `"ẞ"` becomes `"ß"`,
+    /// so it does not match `"ß"` or `"ss"`, and a lone final sigma `"ς"` becomes `"σ"`
+    /// while a word-final one stays `"ς"`.
+    pub fn case_fold(s: &str) -> String {
+        s.to_uppercase().to_lowercase()
+    }
+}
+
+/// Secondary index of a [`UniTable`], created with [`UniTable::create_index`].
+///
+/// Each item has an entry `"{value}\0{key}"` in the index table and the entry under its key in
+/// the reverse table. Lookups match the exact indexed value, `get("ann")` does not return the
+/// items indexed as `"anna"`.
+///
+/// The index holds its own handle of the table, so an index of an
+/// [`OwnedUniTable`](crate::OwnedUniTable) keeps the store alive as well.
+pub struct UniIndex<'a, I: Key, K: Key, V: Value, S: StoreRef = &'a UniStore> {
+    pub table: UniTable<'a, K, V, S>,
+    pub index: UniTable<'a, String, (), S>,
+    pub index_rev: UniTable<'a, String, String, S>,
+    normalize: Option<fn(&str) -> String>,
     phantom: std::marker::PhantomData<I>,
 }
 
-impl<I: Key, K: Key, V: Value> std::fmt::Debug for UniIndex<'_, I, K, V> {
+impl<I: Key, K: Key, V: Value, S: StoreRef> Clone for UniIndex<'_, I, K, V, S> {
+    fn clone(&self) -> Self {
+        UniIndex {
+            table: self.table.clone(),
+            index: self.index.clone(),
+            index_rev: self.index_rev.clone(),
+            normalize: self.normalize,
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<I: Key, K: Key, V: Value, S: StoreRef> std::fmt::Debug for UniIndex<'_, I, K, V, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("UniIndex")
             .field("table", &self.table.name)
@@ -16,24 +56,73 @@ impl<I: Key, K: Key, V: Value> std::fmt::Debug for UniIndex<'_, I, K, V> {
     }
 }
 
-impl<I: Key, K: Key + Clone, V: Value> UniIndex<'_, I, K, V> {
-    pub async fn get(&self, key: impl AsKey<I>) -> Result<Vec<(K, V)>, crate::Error> {
-        let index_entries = self.index.get_prefix(key.as_key().to_key_string()).await?;
-        let mut results = Vec::new();
-        for (index_key, ()) in index_entries {
-            let (_, key) = index_key
-                .split_once('\0')
-                .expect("Index key should contain a separator");
-            let key = K::from_key_string(key)?;
-            if let Some(value) = self.table.get(key.clone()).await? {
-                results.push((key, value));
-            }
+impl<I: Key, K: Key + Clone, V: Value, S: StoreRef> UniIndex<'_, I, K, V, S> {
+    fn value_string(&self, value: impl AsKey<I>) -> String {
+        let value = value.as_key().to_key_string();
+        match self.normalize {
+            Some(normalize) => normalize(&value),
+            None => value,
         }
+    }
+
+    fn entry_prefix(&self, value: impl AsKey<I>) -> String {
+        format!("{}\0", self.value_string(value))
+    }
+
+    /// Returns the keys of all items whose indexed value matches `value`.
+    pub async fn get_keys(&self, value: impl AsKey<I>) -> Result<Vec<K>, crate::Error> {
+        let index_keys = self.index.keys_prefix(self.entry_prefix(value)).await?;
+        index_keys
+            .into_iter()
+            .map(|index_key| {
+                let (_, key) = index_key
+                    .split_once('\0')
+                    .expect("Index key should contain a separator");
+                K::from_key_string(key)
+            })
+            .collect()
+    }
+
+    pub async fn get(&self, key: impl AsKey<I>) -> Result<Vec<(K, V)>, crate::Error> {
+        let keys = self.get_keys(key).await?;
+        let values = self.table.get_many(keys.clone()).await?;
+        let results = keys
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect();
+        Ok(results)
+    }
+
+    /// Looks up multiple index values at once.
+    /// The index lookups of all values run concurrently, and the matching items are fetched
+    /// from the base table in a single batch.
+    /// The result has one entry per requested value, in the same order.
+    pub async fn get_many(
+        &self,
+        values: impl IntoIterator<Item = impl AsKey<I>>,
+    ) -> Result<Vec<Vec<(K, V)>>, crate::Error> {
+        let lookups = values.into_iter().map(|value| self.get_keys(value));
+        let keys = futures::future::try_join_all(lookups).await?;
+        let mut items = self
+            .table
+            .get_many(keys.iter().flatten().cloned())
+            .await?
+            .into_iter();
+        let results = keys
+            .into_iter()
+            .map(|keys| {
+                keys.into_iter()
+                    .zip(items.by_ref())
+                    .filter_map(|(key, value)| value.map(|value| (key, value)))
+                    .collect()
+            })
+            .collect();
         Ok(results)
     }
 
     pub async fn get_first(&self, key: impl AsKey<I>) -> Result<Option<(K, V)>, crate::Error> {
-        let index_entries = self.index.get_prefix(key.as_key().to_key_string()).await?;
+        let index_entries = self.index.get_prefix(self.entry_prefix(key)).await?;
         if index_entries.is_empty() {
             return Ok(None);
         }
@@ -54,7 +143,7 @@ impl<I: Key, K: Key + Clone, V: Value> UniIndex<'_, I, K, V> {
         key: impl AsKey<K>,
     ) -> Result<(), crate::Error> {
         let key_str = key.as_key().to_key_string();
-        let value_str = value.as_key().to_key_string();
+        let value_str = self.value_string(value);
         if let Some(existing) = self.index_rev.get(key_str.as_str()).await? {
             self.index.remove(existing).await?;
         }
@@ -63,26 +152,118 @@ impl<I: Key, K: Key + Clone, V: Value> UniIndex<'_, I, K, V> {
         self.index_rev.insert(key_str, index_key).await?;
         Ok(())
     }
+
+    /// Counts the items whose indexed value matches `value`.
+    /// Only the keys of the index are read, the values in the base table are not loaded.
+    pub async fn count(&self, value: impl AsKey<I>) -> Result<usize, crate::Error> {
+        let index_keys = self.index.keys_prefix(self.entry_prefix(value)).await?;
+        Ok(index_keys.len())
+    }
+
+    /// Removes the index entry for `key`, leaving the base table untouched.
+    pub async fn remove(&self, key: impl AsKey<K>) -> Result<(), crate::Error> {
+        let key_str = key.as_key().to_key_string();
+        if let Some(existing) = self.index_rev.get(key_str.as_str()).await? {
+            self.index.remove(existing).await?;
+            self.index_rev.remove(key_str).await?;
+        }
+        Ok(())
+    }
+
+    /// Removes all items whose indexed value matches `value` from the base table
+    /// together with their entries in all indexes and full-text indexes of the table,
+    /// in a single batch. Relations are not checked, use
+    /// [`UniStoreItem::remove`](crate::UniStoreItem::remove) to apply `on_delete` policies.
+    /// Returns the number of removed items.
+    pub async fn remove_all(&self, value: impl AsKey<I>) -> Result<usize, crate::Error> {
+        let keys = self.get_keys(value).await?;
+        let keys = keys.into_iter().map(|key| self.table.encode_key(key));
+        self.table.remove_keys(keys.collect()).await
+    }
 }
 
-impl<K: Key, V: Value> UniTable<'_, K, V> {
+impl<'a, K: Key, V: Value, S: StoreRef> UniTable<'a, K, V, S> {
     pub async fn create_index<I: Key>(
         &self,
         index: &'static str,
-    ) -> Result<UniIndex<'_, I, K, V>, crate::Error> {
-        let index_table = self
-            .store
-            .create_table(&format!("{}_index_{index}", self.name), false)
-            .await?;
-        let rev_index_table = self
-            .store
-            .create_table(&format!("{}_index_{index}_rev", self.name), false)
+    ) -> Result<UniIndex<'a, I, K, V, S>, crate::Error> {
+        self.open_index(index, None).await
+    }
+
+    /// Creates an index that passes every value through `normalize` before storing or querying it.
+    /// See [`normalize`] for built-in normalizers.
+    pub async fn create_index_normalized<I: Key>(
+        &self,
+        index: &'static str,
+        normalize: fn(&str) -> String,
+    ) -> Result<UniIndex<'a, I, K, V, S>, crate::Error> {
+        self.open_index(index, Some(normalize)).await
+    }
+
+    async fn open_index<I: Key>(
+        &self,
+        index: &'static str,
+        normalize: Option<fn(&str) -> String>,
+    ) -> Result<UniIndex<'a, I, K, V, S>, crate::Error> {
+        let store = self.store.clone();
+        let index_table = UniTable::open(
+            store.clone(),
+            &format!("{}_index_{index}", self.name),
+            false,
+        )
+        .await?;
+        let rev_index_table =
+            UniTable::open(store, &format!("{}_index_{index}_rev", self.name), false).await?;
+        self.store
+            .register_index(&self.name, &index_table.name, false)
             .await?;
         Ok(UniIndex {
-            table: self,
+            table: self.clone(),
             index: index_table,
             index_rev: rev_index_table,
+            normalize,
             phantom: std::marker::PhantomData,
         })
     }
 }
+
+/// Table that lists the index and full-text index tables of each table,
+/// so they are never guessed from table names.
+/// Entries are stored as `"{table}\0{index table}"` with `true` for full-text indexes,
+/// the reverse table of an index is named `"{index table}_rev"`.
+pub(crate) const INDEX_REGISTRY: &str = "__unistore_indexes";
+
+impl UniStore {
+    pub(crate) fn index_registry(&self) -> UniTable<'_, String, bool> {
+        UniTable {
+            store: self,
+            name: INDEX_REGISTRY.to_string(),
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Records `index_table` as an index of `table`.
+    pub(crate) async fn register_index(
+        &self,
+        table: &str,
+        index_table: &str,
+        fulltext: bool,
+    ) -> Result<(), Error> {
+        self.backend.open_table(INDEX_REGISTRY).await?;
+        self.index_registry()
+            .insert(format!("{table}\0{index_table}"), fulltext)
+            .await
+    }
+
+    /// Returns the index tables of `table` and whether they belong to full-text indexes.
+    pub(crate) async fn index_tables(&self, table: &str) -> Result<Vec<(String, bool)>, Error> {
+        let entries = self
+            .index_registry()
+            .get_prefix(format!("{table}\0"))
+            .await?;
+        Ok(entries
+            .into_iter()
+            .map(|(entry, fulltext)| (entry[table.len() + 1..].to_string(), fulltext))
+            .collect())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{AsKey, Error, Key, Value, index::UniIndex};
+use crate::{AsKey, Error, Key, Mutex, UniFullTextIndex, Value, index::UniIndex};
 pub trait UniStoreItem: Value + 'static {
     type Key: Key + 'static;
 
@@ -13,6 +13,14 @@ pub trait UniStoreItem: Value + 'static {
         futures::future::ready(Err(Error::MissingIndex(index)))
     }
 
+    #[must_use]
+    fn fulltext_index(
+        index: &'static str,
+    ) -> impl Future<Output = Result<&'static UniFullTextIndex<'static, Self::Key, Self>, Error>>
+    {
+        futures::future::ready(Err(Error::MissingIndex(index)))
+    }
+
     /// This function is called to insert indices for the item.
     /// It is a no-op by default, but can be overridden in the implementation.
     /// It is called by default when the item is inserted into the table using the traits `insert` method.
@@ -20,6 +28,20 @@ pub trait UniStoreItem: Value + 'static {
         futures::future::ready(Ok(()))
     }
 
+    /// This function is called to remove the index entries of the item with the given key.
+    /// It is a no-op by default, but can be overridden in the implementation.
+    /// It is called by default when the item is removed from the table using the traits `remove` method.
+    fn remove_indices(_key: &Self::Key) -> impl Future<Output = Result<(), Error>> {
+        futures::future::ready(Ok(()))
+    }
+
+    /// This function is called to check that all items referenced by this item exist.
+    /// It is a no-op by default, but can be overridden in the implementation.
+    /// It is called by default before the item is inserted into the table using the traits `save` method.
+    fn check_references(&self) -> impl Future<Output = Result<(), Error>> {
+        futures::future::ready(Ok(()))
+    }
+
     fn get(key: impl AsKey<Self::Key>) -> impl Future<Output = Result<Option<Self>, crate::Error>> {
         async move {
             let table = Self::table().await;
@@ -35,6 +57,49 @@ pub trait UniStoreItem: Value + 'static {
             table.get(key.as_key().to_key_string()).await
         }
     }
+    fn count_by_index(
+        index: &'static str,
+        key: impl AsKey<String>,
+    ) -> impl Future<Output = Result<usize, crate::Error>> {
+        async move {
+            let table = Self::index_table(index).await?;
+            table.count(key).await
+        }
+    }
+    /// Removes all items matching `key` in the given index, including their entries in all other indices.
+    /// Returns the number of removed items.
+    ///
+    /// The relations of all matching items are checked before anything is modified.
+    /// The items and their index entries are then removed together in a single batch,
+    /// after the `on_delete` policies were applied to the referencing items.
+    fn remove_by_index(
+        index: &'static str,
+        key: impl AsKey<String>,
+    ) -> impl Future<Output = Result<usize, crate::Error>> {
+        async move {
+            let table = Self::index_table(index).await?;
+            let keys = table.get_keys(key).await?;
+            for key in &keys {
+                Self::check_remove(key.clone()).await?;
+            }
+            for key in &keys {
+                crate::relation::apply_delete::<Self>(key.clone().to_key_string()).await?;
+            }
+            let table = Self::table().await;
+            let keys = keys.into_iter().map(|key| table.encode_key(key));
+            table.remove_keys(keys.collect()).await
+        }
+    }
+    /// Searches the given full-text index for items containing all words of `query`.
+    fn search(
+        index: &'static str,
+        query: &str,
+    ) -> impl Future<Output = Result<Vec<(Self::Key, Self)>, crate::Error>> {
+        async move {
+            let table = Self::fulltext_index(index).await?;
+            table.search(query).await
+        }
+    }
     fn get_first_by_index(
         index: &'static str,
         key: impl AsKey<String>,
@@ -47,6 +112,7 @@ pub trait UniStoreItem: Value + 'static {
     fn save(&self) -> impl Future<Output = Result<(), crate::Error>> {
         let key = self.unistore_key();
         async move {
+            self.check_references().await?;
             self.insert_indices().await?;
             let table = Self::table().await;
             table.insert(key, self).await
@@ -58,10 +124,109 @@ pub trait UniStoreItem: Value + 'static {
             table.contains(key).await
         }
     }
+    /// Checks that the item with the given key could be removed, i.e. that no `restrict` relation
+    /// references it or an item that would be removed with it by a `cascade` relation.
+    fn check_remove(key: impl AsKey<Self::Key>) -> impl Future<Output = Result<(), crate::Error>> {
+        let key = key.as_key();
+        async move { crate::relation::check_delete::<Self>(key.to_key_string()).await }
+    }
     fn remove(key: impl AsKey<Self::Key>) -> impl Future<Output = Result<(), crate::Error>> {
+        let key = key.as_key();
         async move {
+            crate::relation::on_delete::<Self>(key.clone().to_key_string()).await?;
+            Self::remove_indices(&key).await?;
             let table = Self::table().await;
             table.remove(key).await
         }
     }
 }
+
+/// A value that is created once, used by [`static_store!`](crate::static_store),
+/// [`static_table!`](crate::static_table) and derived items to cache their store, tables
+/// and indexes. Kept in [`static_ref!`](crate::static_ref).
+#[doc(hidden)]
+pub struct OnceCache<T> {
+    value: std::sync::OnceLock<T>,
+    initializing: Mutex<()>,
+}
+
+impl<T> Default for OnceCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> OnceCache<T> {
+    pub const fn new() -> Self {
+        Self {
+            value: std::sync::OnceLock::new(),
+            initializing: Mutex::new(()),
+        }
+    }
+
+    /// Returns the value, creating it with `init` on first use.
+    pub async fn get_or_init<F>(&self, init: impl FnOnce() -> F) -> &T
+    where
+        F: Future<Output = T>,
+    {
+        if let Some(value) = self.value.get() {
+            return value;
+        }
+        let _lock = self.initializing.lock().await;
+        if let Some(value) = self.value.get() {
+            return value;
+        }
+        let _ = self.value.set(init().await);
+        self.value.get().unwrap()
+    }
+}
+
+/// Values that are created once per owner, used by derived items with
+/// `#[unistore(temporary_store = ...)]` to cache their tables and indexes for each of the
+/// separate stores of [`temporary_store!`](crate::temporary_store). The values are leaked.
+#[doc(hidden)]
+pub struct StoreCache<T: 'static> {
+    entries: std::sync::Mutex<Vec<(usize, &'static T)>>,
+    initializing: Mutex<()>,
+}
+
+impl<T> Default for StoreCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> StoreCache<T> {
+    pub const fn new() -> Self {
+        Self {
+            entries: std::sync::Mutex::new(Vec::new()),
+            initializing: Mutex::new(()),
+        }
+    }
+
+    /// Returns the value for `owner`, creating it with `init` on first use.
+    /// The owner has to live for the rest of the program, so its address is never reused.
+    pub async fn get_or_init<O, F>(&self, owner: &'static O, init: impl FnOnce() -> F) -> &'static T
+    where
+        F: Future<Output = T>,
+    {
+        let id = owner as *const O as usize;
+        if let Some(value) = self.get(id) {
+            return value;
+        }
+        let _lock = self.initializing.lock().await;
+        if let Some(value) = self.get(id) {
+            return value;
+        }
+        let value: &'static T = Box::leak(Box::new(init().await));
+        self.entries.lock().unwrap().push((id, value));
+        value
+    }
+
+    fn get(&self, id: usize) -> Option<&'static T> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .find_map(|(owner, value)| (*owner == id).then_some(*value))
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::backend::{Backend, BackendFuture, Entry, KeyBounds, WriteOp, is_empty_range};
+use crate::native::{Error, Worker};
+use crate::{RawValue, UniStoreBuilder};
+
+type Table = BTreeMap<Vec<u8>, RawValue>;
+
+/// The JSON file backend, meant for small datasets, fixtures and debugging.
+///
+/// Each table is kept in memory and stored as a pretty-printed JSON object in `<table>.json`
+/// inside the store directory, with the key strings of [`Key::to_key_string`](crate::Key)
+/// as property names. Table names are percent-encoded in the file names, except for lowercase
+/// ASCII letters, digits, `_` and `-`. The file of a table is rewritten and atomically renamed
+/// into place after every change, so the files can be read or edited while the store is closed.
+/// Keys are ordered as strings and values must be representable as JSON.
+///
+/// Runs on a [`Worker`] that owns the tables.
+pub struct JsonFileBackend(Worker<JsonFiles>);
+
+/// The tables of a [`JsonFileBackend`] and the directory of their files.
+struct JsonFiles {
+    dir: PathBuf,
+    tables: BTreeMap<String, Table>,
+}
+
+impl JsonFileBackend {
+    pub(crate) async fn open(builder: &UniStoreBuilder) -> Result<Self, Error> {
+        let worker = Worker::spawn(builder, "unistore.json", |path| {
+            std::fs::create_dir_all(path)?;
+            JsonFiles::load(Path::new(path))
+        })
+        .await?;
+        Ok(JsonFileBackend(worker))
+    }
+
+    /// Runs `f` on the worker thread with the table, which is empty if it does not exist.
+    async fn read<R: Send + 'static>(
+        &self,
+        table: &str,
+        f: impl FnOnce(&Table) -> R + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        let table = table.to_string();
+        self.0
+            .run(move |files| Ok(f(files.tables.get(&table).unwrap_or(&Table::new()))))
+            .await
+    }
+
+    /// Runs `f` on the worker thread with a copy of the table, which replaces the table once it
+    /// is saved. Returns `None` without calling `f` if the table does not exist.
+    async fn modify<R: Send + 'static>(
+        &self,
+        table: &str,
+        f: impl FnOnce(&mut Table) -> R + Send + 'static,
+    ) -> Result<Option<R>, crate::Error> {
+        let table = table.to_string();
+        self.0
+            .run(move |files| {
+                let Some(mut entries) = files.tables.get(&table).cloned() else {
+                    return Ok(None);
+                };
+                let result = f(&mut entries);
+                files.replace(table, Some(entries))?;
+                Ok(Some(result))
+            })
+            .await
+    }
+}
+
+impl JsonFiles {
+    /// Reads all `*.json` files in `dir`.
+    fn load(dir: &Path) -> Result<Self, Error> {
+        let mut tables = BTreeMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let Some(name) = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .and_then(decode_name)
+            else {
+                continue;
+            };
+            let object: Map<String, Value> = serde_json::from_slice(&std::fs::read(&path)?)?;
+            let table = object
+                .into_iter()
+                .map(|(key, value)| {
+                    Ok((key.into_bytes(), crate::native::encode_named_value(&value)?))
+                })
+                .collect::<Result<_, Error>>()?;
+            tables.insert(name, table);
+        }
+        Ok(JsonFiles {
+            dir: dir.to_path_buf(),
+            tables,
+        })
+    }
+
+    fn file(&self, table: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", encode_name(table)))
+    }
+
+    /// Saves `entries` as the new content of `table`, or deletes the table if it is `None`.
+    fn replace(&mut self, table: String, entries: Option<Table>) -> Result<(), Error> {
+        self.replace_all(vec![(table, entries)])
+    }
+
+    /// Saves the new content of each changed table, deleting the tables that are `None`.
+    ///
+    /// All files are written to temporary files before the first one is renamed into place,
+    /// so a failed write leaves every table unchanged. The renames are not atomic as a group:
+    /// a crash while renaming can leave only some of the tables saved.
+    /// The tables in memory are only changed once their file was replaced, so they always match it.
+    fn replace_all(&mut self, changed: Vec<(String, Option<Table>)>) -> Result<(), Error> {
+        let mut temp_files = Vec::new();
+        for (table, entries) in &changed {
+            let Some(entries) = entries else {
+                continue;
+            };
+            match Self::write_temp(&self.file(table), entries) {
+                Ok(temp_file) => temp_files.push(temp_file),
+                Err(e) => {
+                    for temp_file in temp_files {
+                        let _ = std::fs::remove_file(temp_file);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        let mut temp_files = temp_files.into_iter();
+        for (table, entries) in changed {
+            let path = self.file(&table);
+            match entries {
+                Some(entries) => {
+                    let temp_file = temp_files.next().expect("Temporary file should be written");
+                    std::fs::rename(temp_file, path)?;
+                    self.tables.insert(table, entries);
+                }
+                None => {
+                    match std::fs::remove_file(path) {
+                        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                            return Err(e.into());
+                        }
+                        _ => {}
+                    }
+                    self.tables.remove(&table);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `entries` to the temporary file next to `path` and returns its path.
+    fn write_temp(path: &Path, entries: &Table) -> Result<PathBuf, Error> {
+        let mut object = Map::new();
+        for (key, value) in entries {
+            let key = String::from_utf8(key.clone())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            object.insert(key, crate::native::decode_value(value)?);
+        }
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(&object)?)?;
+        Ok(temp_path)
+    }
+}
+
+/// Percent-encodes every byte of a table name except lowercase ASCII letters, digits, `_` and
+/// `-`, so any name is a file name inside the store directory, and names that only differ in
+/// case don't share a file on case-insensitive file systems.
+fn encode_name(table: &str) -> String {
+    let mut name = String::with_capacity(table.len());
+    for byte in table.bytes() {
+        if is_plain(byte) {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    name
+}
+
+fn is_plain(byte: u8) -> bool {
+    byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_' || byte == b'-'
+}
+
+/// Decodes a file name created by [`encode_name`], returns `None` for other names.
+fn decode_name(name: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else if is_plain(byte) {
+            bytes.push(byte);
+            rest = tail;
+        } else {
+            return None;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+impl Backend for JsonFileBackend {
+    fn string_keys(&self) -> bool {
+        true
+    }
+
+    fn named_values(&self) -> bool {
+        true
+    }
+
+    fn close(&self) {
+        self.0.close();
+    }
+
+    fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool> {
+        let table = table.to_string();
+        Box::pin(self.0.run(move |files| {
+            if files.tables.contains_key(&table) {
+                return Ok(false);
+            }
+            files.replace(table, Some(Table::new()))?;
+            Ok(true)
+        }))
+    }
+
+    fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()> {
+        let table = table.to_string();
+        Box::pin(self.0.run(move |files| files.replace(table, None)))
+    }
+
+    fn list_tables(&self) -> BackendFuture<'_, Vec<String>> {
+        Box::pin(
+            self.0
+                .run(|files| Ok(files.tables.keys().cloned().collect())),
+        )
+    }
+
+    fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>> {
+        Box::pin(self.read(table, move |table| table.get(&key).cloned()))
+    }
+
+    fn insert<'a>(
+        &'a self,
+        table: &'a str,
+        key: Vec<u8>,
+        value: RawValue,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.modify(table, move |entries| entries.insert(key, value))
+                .await?
+                .ok_or_else(|| crate::Error::TableNotFound(table.to_string()))?;
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.modify(table, move |table| table.remove(&key)).await?;
+            Ok(())
+        })
+    }
+
+    fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize> {
+        Box::pin(self.read(table, |table| table.len()))
+    }
+
+    fn scan<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Entry>> {
+        Box::pin(self.read(table, move |table| {
+            if is_empty_range(&range) {
+                return Vec::new();
+            }
+            let items = table.range::<Vec<u8>, _>(range);
+            let items: Box<dyn Iterator<Item = _>> = if reverse {
+                Box::new(items.rev())
+            } else {
+                Box::new(items)
+            };
+            items
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        }))
+    }
+
+    fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>> {
+        Box::pin(async move {
+            let entry = self
+                .modify(table, move |table| {
+                    if last {
+                        table.pop_last()
+                    } else {
+                        table.pop_first()
+                    }
+                })
+                .await?;
+            Ok(entry.flatten())
+        })
+    }
+
+    fn write(&self, batch: Vec<WriteOp>) -> BackendFuture<'_, ()> {
+        Box::pin(self.0.run(move |files| {
+            // The batch is applied to copies of the changed tables, which replace them once saved
+            let mut changed: BTreeMap<String, Table> = BTreeMap::new();
+            for op in batch {
+                let (WriteOp::Insert { table, .. } | WriteOp::Remove { table, .. }) = &op;
+                if !changed.contains_key(table) {
+                    match files.tables.get(table) {
+                        Some(entries) => {
+                            changed.insert(table.clone(), entries.clone());
+                        }
+                        None if matches!(op, WriteOp::Remove { .. }) => continue,
+                        None => return Err(Error::TableNotFound(table.clone())),
+                    }
+                }
+                match op {
+                    WriteOp::Insert { table, key, value } => {
+                        changed.get_mut(&table).unwrap().insert(key, value);
+                    }
+                    WriteOp::Remove { table, key } => {
+                        changed.get_mut(&table).unwrap().remove(&key);
+                    }
+                }
+            }
+            let changed = changed
+                .into_iter()
+                .map(|(table, entries)| (table, Some(entries)))
+                .collect();
+            files.replace_all(changed)
+        }))
+    }
+}
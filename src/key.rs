@@ -1,11 +1,34 @@
 use crate::Error;
 
+/// A key of a [`UniTable`](crate::UniTable).
+///
+/// Tables are ordered by the encoded keys, which depends on the backend: natively keys are
+/// ordered by their [`Key::as_bytes`], on wasm and in backends with
+/// [string keys](crate::Backend::string_keys) by their [`Key::to_key_string`].
+/// Ranges, [`first`](crate::UniTable::first), [`last`](crate::UniTable::last) and the `pop_*`
+/// methods follow this order.
+///
+/// The two orders only agree for strings. Integers are stored big-endian, so unsigned integers
+/// are ordered numerically as bytes, while negative signed integers come after all positive ones.
+/// As strings, integers are ordered by their digits, e.g. `-1, -20, 0, 10, 2`, and a numeric range
+/// like `9..=100` is empty. Use unsigned or zero-padded keys if the order has to be numeric.
 pub trait Key: Sized + Clone {
+    #[allow(clippy::wrong_self_convention)]
     fn as_bytes(self) -> Vec<u8>;
     fn to_key_string(self) -> String;
     fn from_bytes(slice: &[u8]) -> Result<Self, Error>;
     fn from_key_string(s: &str) -> Result<Self, Error>;
 }
+/// Decodes a key stored by a backend with or without [string keys](crate::Backend::string_keys).
+pub(crate) fn decode_key<K: Key>(key: &[u8], string_keys: bool) -> Result<K, Error> {
+    if string_keys {
+        let key = std::str::from_utf8(key).map_err(|e| Error::KeyTypeMismatch(e.to_string()))?;
+        K::from_key_string(key)
+    } else {
+        K::from_bytes(key)
+    }
+}
+
 impl Key for String {
     fn as_bytes(self) -> Vec<u8> {
         self.into_bytes()
@@ -16,7 +39,7 @@ impl Key for String {
     }
 
     fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        Ok(String::from_utf8(bytes.to_vec()).map_err(|e| Error::KeyTypeMismatch(e.to_string()))?)
+        String::from_utf8(bytes.to_vec()).map_err(|e| Error::KeyTypeMismatch(e.to_string()))
     }
 
     fn from_key_string(s: &str) -> Result<Self, Error> {
@@ -1,32 +1,99 @@
+mod aggregate;
+mod backend;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+mod blocking;
+mod builder;
+mod dynamic;
+mod fulltext;
 mod index;
 mod item;
+#[cfg(all(feature = "json-file", not(target_arch = "wasm32")))]
+mod json_file;
 mod key;
+mod memory;
 #[cfg(not(target_arch = "wasm32"))]
 mod native;
+mod owned;
+mod query;
+mod raw;
+#[cfg(all(feature = "redb", not(target_arch = "wasm32")))]
+mod redb;
+mod relation;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod sqlite;
+mod transaction;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
-pub use index::UniIndex;
-pub use item::UniStoreItem;
+pub use backend::{Backend, BackendFuture, Entry, FoldFn, FoldState, KeyBounds, WriteOp};
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub use blocking::{
+    BlockingUniFullTextIndex, BlockingUniIndex, BlockingUniStore, BlockingUniStoreItem,
+    BlockingUniTable,
+};
+pub use builder::UniStoreBuilder;
+pub use dynamic::{DynKey, DynTable, DynValue};
+pub use fulltext::UniFullTextIndex;
+pub use index::{UniIndex, normalize};
+pub use item::{OnceCache, StoreCache, UniStoreItem};
 pub use key::Key;
+pub use memory::MemoryBackend;
+pub use owned::OwnedUniTable;
+pub use query::{FieldFilter, ItemQuery, Query, TypedQuery};
+pub use raw::{RawValue, decode_raw, encode_raw};
+pub use relation::{OnDelete, Relation, RelationFuture};
+pub use transaction::Transaction;
 #[cfg(test)]
 mod tests;
 
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    ops::{Bound, Deref, RangeBounds},
+};
+
+use backend::prefix_range;
+use index::INDEX_REGISTRY;
 
 pub use async_std::sync::Mutex;
+#[doc(hidden)]
+pub use inventory;
 use serde::{Serialize, de::DeserializeOwned};
 pub use unistore_derive::UniStoreItem;
 
 pub trait Value: Serialize + DeserializeOwned {}
 impl<T: Serialize + DeserializeOwned> Value for T {}
 
+/// `Send` on native targets, where closures may run on the storage worker thread.
+/// On wasm everything runs on the same thread, so no bound is required.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// `Sync` on native targets, see [`MaybeSend`].
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSync: Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Sync> MaybeSync for T {}
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSync for T {}
+
+/// A store of typed tables on a [`Backend`].
+///
+/// Stores are `Send` and `Sync` on native targets. On wasm the backends hold JavaScript handles,
+/// so stores stay on their thread, and the store and table macros keep one per thread.
 pub struct UniStore {
-    #[cfg(target_arch = "wasm32")]
-    db: wasm::Database,
-    #[cfg(not(target_arch = "wasm32"))]
-    db: native::Database,
+    backend: Box<dyn Backend>,
     name: String,
+    /// Held while popping from a table with indexes, see [`UniTable::pop_first`].
+    popping: Mutex<()>,
 }
+
 impl std::fmt::Debug for UniStore {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("UniStore")
@@ -35,22 +102,39 @@ impl std::fmt::Debug for UniStore {
     }
 }
 
-pub struct UniTable<'a, K: Key, V: Value> {
-    store: &'a UniStore,
+/// Handle to the [`UniStore`] of a [`UniTable`]: `&UniStore` for tables borrowing their store,
+/// `Arc<UniStore>` for [`OwnedUniTable`]s.
+pub trait StoreRef: Deref<Target = UniStore> + Clone {}
+impl<T: Deref<Target = UniStore> + Clone> StoreRef for T {}
+
+/// A typed table of a [`UniStore`], created by [`UniStore::create_table`].
+///
+/// The table borrows its store by default. With `S = Arc<UniStore>` it keeps the store alive
+/// instead, see [`OwnedUniTable`]. Cloning a table is cheap.
+pub struct UniTable<'a, K: Key, V: Value, S: StoreRef = &'a UniStore> {
+    store: S,
     name: String,
-    #[cfg(not(target_arch = "wasm32"))]
-    table: native::Table,
-    phantom: PhantomData<(K, V)>,
+    phantom: PhantomData<(&'a (), K, V)>,
 }
-impl<K: Key, V: Value> std::fmt::Debug for UniTable<'_, K, V> {
+impl<K: Key, V: Value, S: StoreRef> std::fmt::Debug for UniTable<'_, K, V, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("UniTable")
             .field("name", &self.name)
             .finish_non_exhaustive()
     }
 }
+impl<K: Key, V: Value, S: StoreRef> Clone for UniTable<'_, K, V, S> {
+    fn clone(&self) -> Self {
+        UniTable {
+            store: self.store.clone(),
+            name: self.name.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
 
 pub trait AsKey<K: Key> {
+    #[allow(clippy::wrong_self_convention)]
     fn as_key(self) -> K;
 }
 impl<K: Key> AsKey<K> for K {
@@ -90,13 +174,35 @@ pub enum Error {
     Wasm(String),
     #[cfg(not(target_arch = "wasm32"))]
     #[error("Error in native implementation: {0}")]
-    Native(#[from] native::Error),
+    Native(#[source] native::Error),
     #[error("Missind index for {0}")]
     MissingIndex(&'static str),
     #[error("Key type mismatch: {0}")]
     KeyTypeMismatch(String),
     #[error("Table already exists with different Value type")]
     ValueTypeMismatch(String),
+    #[error("Foreign key violation: {0}")]
+    ForeignKeyViolation(String),
+    #[error("Table not found: {0}")]
+    TableNotFound(String),
+    #[error("Table already exists: {0}")]
+    TableExists(String),
+    #[error("Table belongs to a different store: {0}")]
+    ForeignTable(String),
+    #[error("Invalid store configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Error in storage backend: {0}")]
+    Backend(String),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<native::Error> for Error {
+    fn from(value: native::Error) -> Self {
+        match value {
+            native::Error::TableNotFound(table) => Error::TableNotFound(table),
+            value => Error::Native(value),
+        }
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -107,137 +213,575 @@ impl From<wasm::Error> for Error {
 }
 
 impl UniStore {
+    /// Opens the store in the data directory of the given application.
+    /// Use [`UniStore::builder`] to choose the location and backend options.
     pub async fn new(
         qualifier: &str,
         organization: &str,
         application: &str,
     ) -> Result<Self, Error> {
-        let name = format!("{qualifier}.{organization}.{application}");
-        #[cfg(target_arch = "wasm32")]
-        let db = wasm::create_database(&name).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        let db = native::create_database(qualifier, organization, application).await?;
-        Ok(UniStore { db, name })
+        Self::builder()
+            .app(qualifier, organization, application)
+            .build()
+            .await
+    }
+
+    pub fn builder() -> UniStoreBuilder {
+        UniStoreBuilder::default()
+    }
+
+    /// Opens a store on a custom [`Backend`], `name` is only used for debugging.
+    pub fn with_backend(name: &str, backend: impl Backend + 'static) -> Self {
+        UniStore {
+            backend: Box::new(backend),
+            name: name.to_string(),
+            popping: Mutex::new(()),
+        }
+    }
+
+    /// Opens a store that keeps all data in memory, for tests and ephemeral stores.
+    pub fn in_memory() -> Self {
+        UniStore {
+            backend: Box::new(memory::MemoryBackend::default()),
+            name: "memory".to_string(),
+            popping: Mutex::new(()),
+        }
+    }
+
+    /// Opens a store in a fresh temporary directory that is removed when the store is dropped,
+    /// so tests can use the real backend in isolation. See [`temporary_store!`] for derived items.
+    pub async fn temporary() -> Result<Self, Error> {
+        Self::builder().temporary().build().await
+    }
+
+    /// Waits for pending writes and releases the storage. The directory of a temporary store
+    /// is removed. Later operations on the store and its tables fail.
+    ///
+    /// Stores are closed automatically when dropped, this is only needed for stores that are never
+    /// dropped, like the ones of [`static_store!`] and [`temporary_store!`].
+    pub fn close(&self) {
+        self.backend.close();
     }
 
     pub async fn create_table<K: Key, V: Value>(
         &self,
         name: &str,
         replace_if_incompatible: bool,
-    ) -> Result<UniTable<K, V>, Error> {
-        #[cfg(target_arch = "wasm32")]
-        let table = wasm::create_table(self, name, replace_if_incompatible).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        let table = native::create_table(self, name, replace_if_incompatible).await?;
-        Ok(table)
+    ) -> Result<UniTable<'_, K, V>, Error> {
+        UniTable::open(self, name, replace_if_incompatible).await
+    }
+
+    /// Encodes a key in the format used by the backend.
+    pub(crate) fn encode_key<K: Key>(&self, key: K) -> Vec<u8> {
+        if self.backend.string_keys() {
+            key.to_key_string().into_bytes()
+        } else {
+            key.as_bytes()
+        }
+    }
+
+    pub(crate) fn decode_key<K: Key>(&self, key: &[u8]) -> Result<K, Error> {
+        key::decode_key(key, self.backend.string_keys())
     }
 }
 
-impl<K: Key, V: Value> UniTable<'_, K, V> {
-    pub async fn insert(&self, key: impl AsKey<K>, value: impl AsValue<V>) -> Result<(), Error> {
-        #[cfg(target_arch = "wasm32")]
-        wasm::insert(self, key, value).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        native::insert(self, key, value).await?;
+impl UniStore {
+    /// Returns the names of all tables in the store, including index tables.
+    pub async fn list_tables(&self) -> Result<Vec<String>, Error> {
+        let mut tables = self.backend.list_tables().await?;
+        tables.retain(|table| table != INDEX_REGISTRY);
+        Ok(tables)
+    }
+
+    /// Deletes a table together with its index tables. Does nothing if the table does not exist.
+    /// Handles to the table that are still open read nothing afterwards,
+    /// and inserts through them fail with [`Error::TableNotFound`].
+    pub async fn drop_table(&self, name: &str) -> Result<(), Error> {
+        let mut dropped = vec![name.to_string()];
+        for (index, _) in self.index_tables(name).await? {
+            dropped.push(format!("{index}_rev"));
+            dropped.push(index);
+        }
+        for table in self.list_tables().await? {
+            if dropped.contains(&table) {
+                self.backend.delete_table(&table).await?;
+            }
+        }
+        self.index_registry()
+            .remove_prefix(format!("{name}\0"))
+            .await?;
         Ok(())
     }
 
+    /// Renames a table together with its index tables.
+    /// Fails with [`Error::TableExists`] if `new` or one of the renamed index tables exists.
+    ///
+    /// The entries and the registered indexes move to the new name in one atomic step, see
+    /// [`Backend::rename_tables`]. Handles to the old table that are still open must not be
+    /// used afterwards.
+    pub async fn rename_table(&self, old: &str, new: &str) -> Result<(), Error> {
+        let tables = self.list_tables().await?;
+        if !tables.iter().any(|table| table == old) {
+            return Err(Error::TableNotFound(old.to_string()));
+        }
+        let indexes = self.index_tables(old).await?;
+        let mut renames = vec![(old.to_string(), new.to_string())];
+        let mut batch = Vec::new();
+        for (index, fulltext) in indexes {
+            // Index tables are named after their table, see `UniTable::create_index`
+            let renamed = format!("{new}{}", &index[old.len()..]);
+            batch.push(WriteOp::Remove {
+                table: INDEX_REGISTRY.to_string(),
+                key: self.encode_key(format!("{old}\0{index}")),
+            });
+            batch.push(WriteOp::Insert {
+                table: INDEX_REGISTRY.to_string(),
+                key: self.encode_key(format!("{new}\0{renamed}")),
+                value: raw::encode(&fulltext, self.backend.named_values())?,
+            });
+            renames.push((format!("{index}_rev"), format!("{renamed}_rev")));
+            renames.push((index, renamed));
+        }
+        renames.retain(|(old, _)| tables.contains(old));
+        if let Some((_, new)) = renames.iter().find(|(_, new)| tables.contains(new)) {
+            return Err(Error::TableExists(new.clone()));
+        }
+        self.backend.rename_tables(renames, batch).await
+    }
+}
+
+impl<K: Key, V: Value, S: StoreRef> UniTable<'_, K, V, S> {
+    /// Creates the table in `store`, see [`UniStore::create_table`].
+    pub(crate) async fn open(
+        store: S,
+        name: &str,
+        replace_if_incompatible: bool,
+    ) -> Result<Self, Error> {
+        let table = UniTable {
+            store,
+            name: name.to_string(),
+            phantom: PhantomData,
+        };
+        let backend = table.backend();
+        if backend.open_table(name).await? {
+            return Ok(table);
+        }
+        // If the table is not empty, check if the types match
+        let first = backend
+            .scan(name, (Bound::Unbounded, Bound::Unbounded), false, Some(1))
+            .await?;
+        let Some((key, value)) = first.into_iter().next() else {
+            return Ok(table);
+        };
+        let mismatch = match table.decode_key(&key) {
+            Err(e) => e,
+            Ok(_) => match decode_raw::<V>(value) {
+                Err(e) => Error::ValueTypeMismatch(e.to_string()),
+                Ok(_) => return Ok(table),
+            },
+        };
+        if !replace_if_incompatible {
+            return Err(mismatch);
+        }
+        tracing::warn!("Replacing table {name} due to type mismatch: {mismatch}");
+        backend.delete_table(name).await?;
+        backend.open_table(name).await?;
+        Ok(table)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the handle of the store the table belongs to.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub(crate) fn backend(&self) -> &dyn Backend {
+        self.store.backend.as_ref()
+    }
+
+    pub(crate) fn encode_key(&self, key: K) -> Vec<u8> {
+        self.store.encode_key(key)
+    }
+
+    pub(crate) fn decode_key(&self, key: &[u8]) -> Result<K, Error> {
+        self.store.decode_key(key)
+    }
+
+    pub(crate) fn encode_range(&self, range: impl RangeBounds<K>) -> KeyBounds {
+        (
+            range.start_bound().cloned().map(|key| self.encode_key(key)),
+            range.end_bound().cloned().map(|key| self.encode_key(key)),
+        )
+    }
+
+    pub(crate) fn decode_entry(&self, (key, value): Entry) -> Result<(K, V), Error> {
+        Ok((self.decode_key(&key)?, decode_raw(value)?))
+    }
+
+    pub(crate) fn decode_entries(&self, entries: Vec<Entry>) -> Result<Vec<(K, V)>, Error> {
+        entries
+            .into_iter()
+            .map(|entry| self.decode_entry(entry))
+            .collect()
+    }
+
+    pub async fn insert(&self, key: impl AsKey<K>, value: impl AsValue<V>) -> Result<(), Error> {
+        let key = self.encode_key(key.as_key());
+        let value = raw::encode(&value, self.backend().named_values())?;
+        self.backend().insert(&self.name, key, value).await
+    }
+
     pub async fn contains(&self, key: impl AsKey<K>) -> Result<bool, Error> {
-        #[cfg(target_arch = "wasm32")]
-        let exists = wasm::contains(self, key).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        let exists = native::contains(self, key).await?;
-        Ok(exists)
+        let key = self.encode_key(key.as_key());
+        Ok(self.backend().get(&self.name, key).await?.is_some())
     }
 
     pub async fn get(&self, key: impl AsKey<K>) -> Result<Option<V>, Error> {
-        #[cfg(target_arch = "wasm32")]
-        let value = wasm::get(self, key).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        let value = native::get(self, key).await?;
-        Ok(value)
+        let key = self.encode_key(key.as_key());
+        let value = self.backend().get(&self.name, key).await?;
+        value.map(decode_raw).transpose()
+    }
+
+    /// Fetches multiple values in a single round-trip to the backend.
+    /// The result has one entry per requested key, in the same order.
+    pub async fn get_many(
+        &self,
+        keys: impl IntoIterator<Item = impl AsKey<K>>,
+    ) -> Result<Vec<Option<V>>, Error> {
+        let keys = keys
+            .into_iter()
+            .map(|key| self.encode_key(key.as_key()))
+            .collect();
+        let values = self.backend().get_many(&self.name, keys).await?;
+        values
+            .into_iter()
+            .map(|value| value.map(decode_raw).transpose())
+            .collect()
     }
 
     pub async fn remove(&self, key: impl AsKey<K>) -> Result<(), Error> {
-        #[cfg(target_arch = "wasm32")]
-        wasm::remove(self, key).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        native::remove(self, key).await?;
-        Ok(())
+        let key = self.encode_key(key.as_key());
+        self.backend().remove(&self.name, key).await
     }
 
     pub async fn len(&self) -> Result<usize, Error> {
-        #[cfg(target_arch = "wasm32")]
-        let count = wasm::len(self).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        let count = native::len(self).await?;
-        Ok(count)
+        self.backend().len(&self.name).await
     }
 
     pub async fn is_empty(&self) -> Result<bool, Error> {
-        #[cfg(target_arch = "wasm32")]
-        let empty = wasm::is_empty(self).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        let empty = native::is_empty(self).await?;
-        Ok(empty)
+        let first = self
+            .backend()
+            .scan(
+                &self.name,
+                (Bound::Unbounded, Bound::Unbounded),
+                false,
+                Some(1),
+            )
+            .await?;
+        Ok(first.is_empty())
     }
 
     pub async fn get_prefix(&self, prefix: impl AsKey<K>) -> Result<Vec<(K, V)>, Error> {
-        #[cfg(target_arch = "wasm32")]
-        let values = wasm::get_prefix(self, prefix).await?;
-        #[cfg(not(target_arch = "wasm32"))]
-        let values = native::get_prefix(self, prefix).await?;
-        Ok(values)
+        let range = prefix_range(self.encode_key(prefix.as_key()));
+        let entries = self.backend().scan(&self.name, range, false, None).await?;
+        self.decode_entries(entries)
+    }
+
+    /// Returns all entries with keys inside `range`, ordered by key.
+    /// The order of the keys depends on the backend, see [`Key`].
+    pub async fn get_range(&self, range: impl RangeBounds<K>) -> Result<Vec<(K, V)>, Error> {
+        let range = self.encode_range(range);
+        let entries = self.backend().scan(&self.name, range, false, None).await?;
+        self.decode_entries(entries)
+    }
+
+    /// Returns all keys of the table, ordered by key.
+    /// Only the keys are transferred and decoded, the values are never deserialized.
+    pub async fn keys(&self) -> Result<Vec<K>, Error> {
+        self.keys_range(..).await
+    }
+
+    /// Returns the keys starting with `prefix`, ordered by key.
+    pub async fn keys_prefix(&self, prefix: impl AsKey<K>) -> Result<Vec<K>, Error> {
+        let range = prefix_range(self.encode_key(prefix.as_key()));
+        self.scan_keys(range).await
+    }
+
+    /// Returns the keys inside `range`, ordered by key.
+    pub async fn keys_range(&self, range: impl RangeBounds<K>) -> Result<Vec<K>, Error> {
+        self.scan_keys(self.encode_range(range)).await
+    }
+
+    async fn scan_keys(&self, range: KeyBounds) -> Result<Vec<K>, Error> {
+        let keys = self
+            .backend()
+            .scan_keys(&self.name, range, false, None)
+            .await?;
+        keys.iter().map(|key| self.decode_key(key)).collect()
+    }
+
+    /// Removes all entries of the table and its indexes.
+    /// Returns the number of removed entries.
+    pub async fn clear(&self) -> Result<usize, Error> {
+        self.remove_range(..).await
+    }
+
+    /// Removes all entries with keys starting with `prefix`, including their index entries.
+    /// Relations are not checked, use [`UniStoreItem::remove`] to apply `on_delete` policies.
+    pub async fn remove_prefix(&self, prefix: impl AsKey<K>) -> Result<usize, Error> {
+        let range = prefix_range(self.encode_key(prefix.as_key()));
+        self.remove_bounds(range).await
+    }
+
+    /// Removes all entries with keys inside `range`, including their index entries.
+    /// Relations are not checked, use [`UniStoreItem::remove`] to apply `on_delete` policies.
+    pub async fn remove_range(&self, range: impl RangeBounds<K>) -> Result<usize, Error> {
+        self.remove_bounds(self.encode_range(range)).await
+    }
+
+    /// Removes the entries inside `range`, see [`UniTable::remove_keys`].
+    async fn remove_bounds(&self, range: KeyBounds) -> Result<usize, Error> {
+        let keys = self
+            .backend()
+            .scan_keys(&self.name, range, false, None)
+            .await?;
+        self.remove_keys(keys).await
+    }
+
+    /// Removes the entries with the given encoded keys in a single batch, see
+    /// [`UniTable::removal_batch`].
+    pub(crate) async fn remove_keys(&self, keys: Vec<Vec<u8>>) -> Result<usize, Error> {
+        let count = keys.len();
+        let batch = self.removal_batch(keys).await?;
+        self.backend().write(batch).await?;
+        Ok(count)
+    }
+
+    /// Returns the writes that remove the entries with the given encoded keys together with
+    /// their entries in the table's indexes and full-text indexes,
+    /// using the reverse tables to find the entries of each removed key.
+    pub(crate) async fn removal_batch(&self, keys: Vec<Vec<u8>>) -> Result<Vec<WriteOp>, Error> {
+        let key_strings = keys
+            .iter()
+            .map(|key| Ok(self.decode_key(key)?.to_key_string()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut batch = Vec::new();
+        for (index, fulltext) in self.store.index_tables(&self.name).await? {
+            let rev = format!("{index}_rev");
+            let rev_keys = key_strings
+                .iter()
+                .map(|key_str| self.store.encode_key(key_str.clone()))
+                .collect::<Vec<_>>();
+            let values = self.backend().get_many(&rev, rev_keys.clone()).await?;
+            for ((key_str, rev_key), value) in key_strings.iter().zip(rev_keys).zip(values) {
+                let Some(value) = value else {
+                    continue;
+                };
+                // A regular index stores its entry, a full-text index the indexed words
+                let entries = if fulltext {
+                    let words: Vec<String> = decode_raw(value)?;
+                    words
+                        .into_iter()
+                        .map(|word| format!("{word}\0{key_str}"))
+                        .collect()
+                } else {
+                    vec![decode_raw::<String>(value)?]
+                };
+                batch.extend(entries.into_iter().map(|entry| WriteOp::Remove {
+                    table: index.clone(),
+                    key: self.store.encode_key(entry),
+                }));
+                batch.push(WriteOp::Remove {
+                    table: rev.clone(),
+                    key: rev_key,
+                });
+            }
+        }
+        batch.extend(keys.into_iter().map(|key| WriteOp::Remove {
+            table: self.name.clone(),
+            key,
+        }));
+        Ok(batch)
+    }
+
+    /// Returns the entry with the smallest key.
+    pub async fn first(&self) -> Result<Option<(K, V)>, Error> {
+        self.edge(false).await
+    }
+
+    /// Returns the entry with the largest key.
+    pub async fn last(&self) -> Result<Option<(K, V)>, Error> {
+        self.edge(true).await
+    }
+
+    async fn edge(&self, last: bool) -> Result<Option<(K, V)>, Error> {
+        let entries = self
+            .backend()
+            .scan(
+                &self.name,
+                (Bound::Unbounded, Bound::Unbounded),
+                last,
+                Some(1),
+            )
+            .await?;
+        let entry = entries.into_iter().next();
+        entry.map(|entry| self.decode_entry(entry)).transpose()
+    }
+
+    /// Atomically removes and returns the entry with the smallest key.
+    ///
+    /// The entries of the table's indexes and full-text indexes are removed in the same write.
+    /// Tables without indexes are popped by the backend in one step. For tables with indexes the
+    /// entry is read first, and the pops of the store run one at a time, so concurrent pops
+    /// never return the same entry.
+    pub async fn pop_first(&self) -> Result<Option<(K, V)>, Error> {
+        self.pop(false).await
+    }
+
+    /// Atomically removes and returns the entry with the largest key, see [`Self::pop_first`].
+    pub async fn pop_last(&self) -> Result<Option<(K, V)>, Error> {
+        self.pop(true).await
+    }
+
+    async fn pop(&self, last: bool) -> Result<Option<(K, V)>, Error> {
+        if self.store.index_tables(&self.name).await?.is_empty() {
+            let entry = self.backend().pop(&self.name, last).await?;
+            return entry.map(|entry| self.decode_entry(entry)).transpose();
+        }
+        let _lock = self.store.popping.lock().await;
+        let entries = self
+            .backend()
+            .scan(
+                &self.name,
+                (Bound::Unbounded, Bound::Unbounded),
+                last,
+                Some(1),
+            )
+            .await?;
+        let Some((key, value)) = entries.into_iter().next() else {
+            return Ok(None);
+        };
+        let batch = self.removal_batch(vec![key.clone()]).await?;
+        self.backend().write(batch).await?;
+        self.decode_entry((key, value)).map(Some)
     }
 }
 
+/// Returns a `&'static` reference to a value of type `$ty` that is created with `$init` on first use.
+///
+/// Natively the value is kept in a static shared by all threads. On wasm stores and tables are
+/// not `Sync`, so every thread creates and leaks its own value instead.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! static_ref {
+    ($ty:ty, $init:expr) => {{
+        #[cfg(not(target_arch = "wasm32"))]
+        let value: &'static $ty = {
+            static VALUE: std::sync::LazyLock<$ty> = std::sync::LazyLock::new(|| $init);
+            &VALUE
+        };
+        #[cfg(target_arch = "wasm32")]
+        let value: &'static $ty = {
+            thread_local! {
+                static VALUE: &'static $ty = Box::leak(Box::new($init));
+            }
+            VALUE.with(|value| *value)
+        };
+        value
+    }};
+}
+
 #[macro_export]
 macro_rules! static_table {
     ($fn_name:ident, $name:literal, $key:ty, $val:ty, $get_store: path) => {
         async fn $fn_name() -> &'static $crate::UniTable<'static, $key, $val> {
-            use $crate::Mutex;
-            static TABLE: std::sync::OnceLock<$crate::UniTable<'static, $key, $val>> =
-                std::sync::OnceLock::new();
-            static INITIALIZING: Mutex<()> = Mutex::new(());
-
-            if let Some(table) = TABLE.get() {
-                return table;
-            }
-            let _lock = INITIALIZING.lock().await;
-            if let Some(table) = TABLE.get() {
-                return table;
-            }
-            let store = $get_store().await;
-            let table = store
-                .create_table::<$key, $val>($name, true)
+            let cache = $crate::static_ref!(
+                $crate::OnceCache<$crate::UniTable<'static, $key, $val>>,
+                $crate::OnceCache::new()
+            );
+            cache
+                .get_or_init(|| async {
+                    $get_store()
+                        .await
+                        .create_table::<$key, $val>($name, true)
+                        .await
+                        .expect("Failed to create table")
+                })
                 .await
-                .expect("Failed to create table");
-            TABLE.set(table).expect("Failed to set table");
-            TABLE.get().unwrap()
         }
     };
 }
 
+/// Defines an async function returning a store that is opened on first use.
+///
+/// The store is either opened in the data directory of an application,
+/// `static_store!(get_store, "com", "example", "app")`, kept in memory with
+/// `static_store!(get_store, memory)`, or built from a [`UniStoreBuilder`] expression,
+/// e.g. `static_store!(get_store, UniStore::builder().path("data"))`.
 #[macro_export]
 macro_rules! static_store {
+    ($fn_name:ident, memory) => {
+        async fn $fn_name() -> &'static $crate::UniStore {
+            $crate::static_ref!($crate::UniStore, $crate::UniStore::in_memory())
+        }
+    };
     ($fn_name:ident, $qualifier:literal, $organization:literal, $application:literal) => {
+        $crate::static_store!(
+            $fn_name,
+            $crate::UniStore::builder().app($qualifier, $organization, $application)
+        );
+    };
+    ($fn_name:ident, $builder:expr) => {
         async fn $fn_name() -> &'static $crate::UniStore {
-            use $crate::Mutex;
-            static STORE: std::sync::OnceLock<$crate::UniStore> = std::sync::OnceLock::new();
-            static INITIALIZING: Mutex<()> = Mutex::new(());
+            let cache = $crate::static_ref!(
+                $crate::OnceCache<$crate::UniStore>,
+                $crate::OnceCache::new()
+            );
+            cache
+                .get_or_init(|| async { $builder.build().await.expect("Failed to create store") })
+                .await
+        }
+    };
+}
 
-            if let Some(store) = STORE.get() {
-                return store;
+/// Defines an async function returning a store for tests, which can be used as
+/// `#[unistore(temporary_store = ...)]` of derived items.
+///
+/// Each thread gets its own [`UniStore::temporary`] store, which is closed and removed when the
+/// thread exits. As every test runs on its own thread, tests are isolated from each other
+/// and don't need to run sequentially.
+///
+/// Derived items with `temporary_store` keep their tables and indexes for each store,
+/// where items with `store = ...` create them only once. The cached tables are never freed.
+#[macro_export]
+macro_rules! temporary_store {
+    ($fn_name:ident) => {
+        async fn $fn_name() -> &'static $crate::UniStore {
+            struct Guard(std::cell::Cell<Option<&'static $crate::UniStore>>);
+            impl Drop for Guard {
+                fn drop(&mut self) {
+                    if let Some(store) = self.0.get() {
+                        store.close();
+                    }
+                }
             }
-            let _lock = INITIALIZING.lock().await;
-            if let Some(store) = STORE.get() {
+            thread_local! {
+                static STORE: Guard = const { Guard(std::cell::Cell::new(None)) };
+            }
+
+            if let Some(store) = STORE.with(|guard| guard.0.get()) {
                 return store;
             }
-            let store = $crate::UniStore::new($qualifier, $organization, $application)
+            let store = $crate::UniStore::temporary()
                 .await
                 .expect("Failed to create store");
-            STORE.set(store).expect("Failed to set store");
-            STORE.get().unwrap()
+            // Leaked so that tables can borrow it for 'static, the guard releases the storage
+            let store: &'static $crate::UniStore = Box::leak(Box::new(store));
+            STORE.with(|guard| guard.0.set(Some(store)));
+            store
         }
     };
 }
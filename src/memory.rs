@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::{Error, RawValue};
+
+use crate::backend::{Backend, BackendFuture, Entry, KeyBounds, WriteOp, is_empty_range};
+
+type Table = BTreeMap<Vec<u8>, RawValue>;
+
+/// A backend keeping all tables in memory, e.g. for tests and caches.
+///
+/// Keys and values are encoded like in the default backend of the target,
+/// so ordering and serialization behave the same. All data is lost when the store is dropped.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    tables: Mutex<BTreeMap<String, Table>>,
+}
+
+impl MemoryBackend {
+    /// Runs `f` with the table, or returns `default` if it does not exist.
+    fn with_table<R>(&self, table: &str, default: R, f: impl FnOnce(&mut Table) -> R) -> R {
+        match self.tables.lock().unwrap().get_mut(table) {
+            Some(table) => f(table),
+            None => default,
+        }
+    }
+}
+
+/// Returns the entries of `table` inside `range`.
+/// Unlike [`BTreeMap::range`] this does not panic on empty or inverted ranges.
+fn entries<'a>(
+    table: &'a Table,
+    range: &KeyBounds,
+) -> Box<dyn DoubleEndedIterator<Item = (&'a Vec<u8>, &'a RawValue)> + 'a> {
+    if is_empty_range(range) {
+        return Box::new(std::iter::empty());
+    }
+    Box::new(table.range::<Vec<u8>, _>(range.clone()))
+}
+
+impl Backend for MemoryBackend {
+    fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool> {
+        let mut tables = self.tables.lock().unwrap();
+        let new = !tables.contains_key(table);
+        tables.entry(table.to_string()).or_default();
+        Box::pin(async move { Ok(new) })
+    }
+
+    fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()> {
+        self.tables.lock().unwrap().remove(table);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn list_tables(&self) -> BackendFuture<'_, Vec<String>> {
+        let tables = self.tables.lock().unwrap().keys().cloned().collect();
+        Box::pin(async move { Ok(tables) })
+    }
+
+    fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>> {
+        let value = self.with_table(table, None, |table| table.get(&key).cloned());
+        Box::pin(async move { Ok(value) })
+    }
+
+    fn insert<'a>(
+        &'a self,
+        table: &'a str,
+        key: Vec<u8>,
+        value: RawValue,
+    ) -> BackendFuture<'a, ()> {
+        let result = match self.tables.lock().unwrap().get_mut(table) {
+            Some(entries) => {
+                entries.insert(key, value);
+                Ok(())
+            }
+            None => Err(Error::TableNotFound(table.to_string())),
+        };
+        Box::pin(async { result })
+    }
+
+    fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()> {
+        self.with_table(table, None, |table| table.remove(&key));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize> {
+        let len = self.with_table(table, 0, |table| table.len());
+        Box::pin(async move { Ok(len) })
+    }
+
+    fn scan<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Entry>> {
+        let entries = self.with_table(table, Vec::new(), |table| {
+            let items = entries(table, &range);
+            let items: Box<dyn Iterator<Item = _>> = if reverse {
+                Box::new(items.rev())
+            } else {
+                Box::new(items)
+            };
+            items
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        });
+        Box::pin(async move { Ok(entries) })
+    }
+
+    fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>> {
+        let entry = self.with_table(table, None, |table| {
+            if last {
+                table.pop_last()
+            } else {
+                table.pop_first()
+            }
+        });
+        Box::pin(async move { Ok(entry) })
+    }
+
+    fn write(&self, batch: Vec<WriteOp>) -> BackendFuture<'_, ()> {
+        // The lock is held for the whole batch, so it is applied atomically.
+        let mut tables = self.tables.lock().unwrap();
+        let missing = batch.iter().find_map(|op| match op {
+            WriteOp::Insert { table, .. } if !tables.contains_key(table) => Some(table.clone()),
+            _ => None,
+        });
+        if let Some(table) = missing {
+            return Box::pin(async { Err(Error::TableNotFound(table)) });
+        }
+        for op in batch {
+            match op {
+                WriteOp::Insert { table, key, value } => {
+                    tables.get_mut(&table).unwrap().insert(key, value);
+                }
+                WriteOp::Remove { table, key } => {
+                    if let Some(entries) = tables.get_mut(&table) {
+                        entries.remove(&key);
+                    }
+                }
+            }
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -1,21 +1,28 @@
-use fjall::{Keyspace, PartitionCreateOptions, PartitionHandle, Slice};
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::Mutex,
+    thread::JoinHandle,
+};
+
+use fjall::{Keyspace, PartitionCreateOptions, PartitionHandle};
 use futures::{
     SinkExt,
     channel::{mpsc, oneshot},
     executor::block_on_stream,
 };
+use serde::Serialize;
 use tracing::info;
 
-use crate::{AsKey, AsValue, Key, UniStore, UniTable, Value};
-
-pub type Table = PartitionHandle;
+use crate::backend::{
+    Backend, BackendFuture, Entry, FoldFn, FoldState, KeyBounds, WriteOp, fold_entries,
+};
+use crate::{RawValue, UniStoreBuilder, Value};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Fjall error: {0}")]
     Fjall(#[from] fjall::Error),
-    #[error("Store is not initialized")]
-    StoreNotInitialized,
     #[error("Sending to mpsc channel failed: {0}")]
     Mpsc(#[from] mpsc::SendError),
     #[error("Receiving from oneshot channel failed: {0}")]
@@ -26,481 +33,417 @@ pub enum Error {
     RmpDecode(#[from] rmp_serde::decode::Error),
     #[error("Data directory not found")]
     DataDirNotFound,
+    #[error("Table not found: {0}")]
+    TableNotFound(String),
+    #[error("Storage operation panicked: {0}")]
+    Panicked(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "json-file")]
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "redb")]
+    #[error("redb error: {0}")]
+    Redb(Box<redb::Error>),
+}
+
+/// A closure executed on the worker thread that owns the database.
+type Job<S> = Box<dyn FnOnce(&mut S) + Send>;
+
+/// Returns the message of a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
 }
 
-fn get_path(qualifier: &str, organization: &str, application: &str) -> Result<String, Error> {
-    let base_dirs = robius_directories::ProjectDirs::from(qualifier, organization, application)
-        .ok_or(Error::DataDirNotFound)?;
-    let data_dir = base_dirs.data_dir();
-    let path = data_dir
-        .join("unistore.fjall")
-        .to_string_lossy()
-        .to_string();
+/// Returns the path of the database file, in a fresh directory for temporary stores.
+fn get_path(
+    builder: &UniStoreBuilder,
+    default_file_name: &str,
+    temp_dir: Option<&tempfile::TempDir>,
+) -> Result<String, Error> {
+    let data_dir = match (temp_dir, &builder.path, &builder.app) {
+        (Some(temp_dir), _, _) => temp_dir.path().to_path_buf(),
+        (None, Some(path), _) => path.clone(),
+        (None, None, Some((qualifier, organization, application))) => {
+            let base_dirs =
+                robius_directories::ProjectDirs::from(qualifier, organization, application)
+                    .ok_or(Error::DataDirNotFound)?;
+            base_dirs.data_dir().to_path_buf()
+        }
+        (None, None, None) => return Err(Error::DataDirNotFound),
+    };
+    let file_name = builder.file_name.as_deref().unwrap_or(default_file_name);
+    let path = data_dir.join(file_name).to_string_lossy().to_string();
     info!("Storage path: {path}");
     Ok(path)
 }
 
-pub struct Database(mpsc::Sender<Action>);
-impl Database {
-    pub async fn create_table(&self, name: &str) -> Result<(PartitionHandle, bool), Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::CreateTable {
-            name: name.to_string(),
-            resp_tx,
-        })
-        .await?;
-        resp_rx.await?
+fn get_config(builder: &UniStoreBuilder, path: &str) -> fjall::Config {
+    let mut config = fjall::Config::new(path);
+    if let Some(bytes) = builder.cache_size {
+        config = config.cache_size(bytes);
     }
-
-    async fn is_table_empty(&self, table: PartitionHandle) -> Result<bool, Error> {
-        tracing::info!("Checking if table is empty: {}", table.name);
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::IsTableEmpty { table, resp_tx }).await?;
-        resp_rx.await?
+    if let Some(bytes) = builder.write_buffer_size {
+        config = config.max_write_buffer_size(bytes);
     }
-
-    async fn first_key_value(
-        &self,
-        table: PartitionHandle,
-    ) -> Result<Option<(Slice, Slice)>, Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::FirstKeyValue { table, resp_tx }).await?;
-        resp_rx.await?
+    if let Some(ms) = builder.fsync_ms {
+        config = config.fsync_ms(ms);
     }
+    config
+}
 
-    async fn delete_table(&self, table: PartitionHandle) -> Result<(), Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::DeleteTable { table, resp_tx }).await?;
-        resp_rx.await?
+/// A thread that owns a blocking database and runs all operations on it,
+/// so the async API of the native backends never blocks the executor.
+pub(crate) struct Worker<S> {
+    tx: mpsc::Sender<Job<S>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    /// Directory of a temporary store, removed when the worker is closed.
+    temp_dir: Mutex<Option<tempfile::TempDir>>,
+}
+
+impl<S> Worker<S> {
+    /// Waits for the queued jobs and drops the database, later jobs fail.
+    pub(crate) fn close(&self) {
+        self.tx.clone().close_channel();
+        let thread = self.thread.lock().unwrap().take();
+        if thread.is_some_and(|thread| thread.join().is_err()) {
+            tracing::warn!("Storage worker thread panicked");
+        }
+        self.temp_dir.lock().unwrap().take();
     }
+}
 
-    async fn contains(&self, table: PartitionHandle, key: Slice) -> Result<bool, Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::Contains {
-            table,
-            key,
-            resp_tx,
+impl<S: 'static> Worker<S> {
+    /// Spawns the worker thread and opens the database at the path configured in `builder`
+    /// with `open`.
+    pub(crate) async fn spawn(
+        builder: &UniStoreBuilder,
+        default_file_name: &str,
+        open: impl FnOnce(&str) -> Result<S, Error> + Send + 'static,
+    ) -> Result<Self, Error> {
+        let temp_dir = match builder.temporary {
+            true => Some(tempfile::Builder::new().prefix("unistore").tempdir()?),
+            false => None,
+        };
+        let path = get_path(builder, default_file_name, temp_dir.as_ref())?;
+        let (tx, rx) = mpsc::channel::<Job<S>>(builder.channel_capacity);
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let thread = std::thread::spawn(move || {
+            let mut db = match open(&path) {
+                Ok(db) => db,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            if ready_tx.send(Ok(())).is_err() {
+                return;
+            }
+            for job in block_on_stream(rx) {
+                job(&mut db);
+            }
+        });
+        ready_rx.await??;
+        Ok(Worker {
+            tx,
+            thread: Mutex::new(Some(thread)),
+            temp_dir: Mutex::new(temp_dir),
         })
-        .await?;
-        resp_rx.await?
     }
 
-    async fn insert(&self, table: PartitionHandle, key: Slice, value: Slice) -> Result<(), Error> {
-        let mut tx = self.0.clone();
+    /// Runs `f` on the worker thread and returns its result.
+    ///
+    /// A panic in `f` is returned as [`Error::Panicked`] and the worker keeps running.
+    /// `f` must not use the store: its operations are queued behind `f` and never run.
+    pub(crate) async fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut S) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        let mut tx = self.tx.clone();
         let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::Insert {
-            table,
-            key,
-            value,
-            resp_tx,
-        })
-        .await?;
-        resp_rx.await?
+        tx.send(Box::new(move |db| {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(db)))
+                .unwrap_or_else(|payload| Err(Error::Panicked(panic_message(payload.as_ref()))));
+            if resp_tx.send(result).is_err() {
+                tracing::warn!("Failed to send response for action");
+            }
+        }))
+        .await
+        .map_err(Error::from)?;
+        let result = resp_rx.await.map_err(Error::from)?;
+        Ok(result?)
+    }
+}
+
+impl<S> Drop for Worker<S> {
+    fn drop(&mut self) {
+        self.close();
     }
+}
 
-    async fn get(&self, table: PartitionHandle, key: Slice) -> Result<Option<Slice>, Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::Get {
-            table,
-            key,
-            resp_tx,
+/// The fjall backend, running on a [`Worker`] that owns the keyspace.
+pub struct FjallBackend(Worker<Keyspace>);
+
+impl FjallBackend {
+    pub(crate) async fn open(builder: &UniStoreBuilder) -> Result<Self, Error> {
+        let options = builder.clone();
+        let worker = Worker::spawn(builder, "unistore.fjall", move |path| {
+            Ok(get_config(&options, path).open()?)
         })
         .await?;
-        resp_rx.await?
+        Ok(FjallBackend(worker))
     }
 
-    async fn len(&self, table: PartitionHandle) -> Result<usize, Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::Len { table, resp_tx }).await?;
-        resp_rx.await?
+    async fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Keyspace) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        self.0.run(move |ks| f(ks)).await
     }
 
-    async fn remove(&self, table: PartitionHandle, key: Slice) -> Result<(), Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::Remove {
-            table,
-            key,
-            resp_tx,
+    /// Runs `f` on the worker thread with the partition of `table`, which must exist.
+    async fn run_on<R: Send + 'static>(
+        &self,
+        table: &str,
+        f: impl FnOnce(&PartitionHandle) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        let table = table.to_string();
+        self.run(move |ks| match existing_partition(ks, &table)? {
+            Some(partition) => f(&partition),
+            None => Err(Error::TableNotFound(table)),
         })
-        .await?;
-        resp_rx.await?
+        .await
     }
 
-    async fn prefix(
+    /// Runs `f` on the worker thread with the partition of `table`,
+    /// or returns `default` if it does not exist.
+    async fn read_on<R: Send + 'static>(
         &self,
-        table: PartitionHandle,
-        prefix: Vec<u8>,
-    ) -> Result<Vec<Result<(Slice, Slice), fjall::Error>>, Error> {
-        let mut tx = self.0.clone();
-        let (resp_tx, resp_rx) = oneshot::channel();
-        tx.send(Action::GetPrefix {
-            table,
-            prefix,
-            resp_tx,
+        table: &str,
+        default: R,
+        f: impl FnOnce(&PartitionHandle) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        let table = table.to_string();
+        self.run(move |ks| match existing_partition(ks, &table)? {
+            Some(partition) => f(&partition),
+            None => Ok(default),
         })
-        .await?;
-        Ok(resp_rx.await?)
+        .await
     }
 }
 
-enum Action {
-    CreateDb {
-        qualifier: String,
-        organization: String,
-        application: String,
-        resp_tx: oneshot::Sender<Result<(), Error>>,
-    },
-    CreateTable {
-        name: String,
-        resp_tx: oneshot::Sender<Result<(PartitionHandle, bool), Error>>,
-    },
-    IsTableEmpty {
-        table: PartitionHandle,
-        resp_tx: oneshot::Sender<Result<bool, Error>>,
-    },
-    FirstKeyValue {
-        table: PartitionHandle,
-        resp_tx: oneshot::Sender<Result<Option<(Slice, Slice)>, Error>>,
-    },
-    DeleteTable {
-        table: PartitionHandle,
-        resp_tx: oneshot::Sender<Result<(), Error>>,
-    },
-    Insert {
-        table: PartitionHandle,
-        key: Slice,
-        value: Slice,
-        resp_tx: oneshot::Sender<Result<(), Error>>,
-    },
-    Get {
-        table: PartitionHandle,
-        key: Slice,
-        resp_tx: oneshot::Sender<Result<Option<Slice>, Error>>,
-    },
-    Contains {
-        table: PartitionHandle,
-        key: Slice,
-        resp_tx: oneshot::Sender<Result<bool, Error>>,
-    },
-    Len {
-        table: PartitionHandle,
-        resp_tx: oneshot::Sender<Result<usize, Error>>,
-    },
-    Remove {
-        table: PartitionHandle,
-        key: Slice,
-        resp_tx: oneshot::Sender<Result<(), Error>>,
-    },
-    GetPrefix {
-        table: PartitionHandle,
-        prefix: Vec<u8>,
-        resp_tx: oneshot::Sender<Vec<Result<(Slice, Slice), fjall::Error>>>,
-    },
+fn partition(ks: &Keyspace, name: &str) -> Result<PartitionHandle, Error> {
+    Ok(ks.open_partition(name, PartitionCreateOptions::default())?)
 }
-impl std::fmt::Debug for Action {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Action::CreateDb {
-                qualifier,
-                organization,
-                application,
-                ..
-            } => write!(f, "CreateDb({qualifier}.{organization}.{application})"),
-            Action::CreateTable { name, .. } => write!(f, "CreateTable({name})"),
-            Action::IsTableEmpty { .. } => write!(f, "IsTableEmpty"),
-            Action::FirstKeyValue { .. } => write!(f, "FirstKeyValue"),
-            Action::DeleteTable { .. } => write!(f, "DeleteTable"),
-            Action::Insert {
-                table, key, value, ..
-            } => {
-                write!(
-                    f,
-                    "Insert(table: {}, key: {:?}, value: {:?})",
-                    table.name, key, value
-                )
-            }
-            Action::Get { table, key, .. } => {
-                write!(f, "Get(table: {}, key: {:?})", table.name, key)
-            }
-            Action::Contains { table, key, .. } => {
-                write!(f, "Contains(table: {}, key: {:?})", table.name, key)
-            }
-            Action::Len { table, .. } => write!(f, "Count(table: {})", table.name),
-            Action::Remove { table, key, .. } => {
-                write!(f, "Remove(table: {}, key: {:?})", table.name, key)
-            }
-            Action::GetPrefix { prefix, .. } => {
-                write!(f, "GetPrefix(prefix: {prefix:?})")
-            }
-        }
+
+/// Opens the partition only if it exists, so a dropped table is not created again.
+fn existing_partition(ks: &Keyspace, name: &str) -> Result<Option<PartitionHandle>, Error> {
+    if !ks.partition_exists(name) {
+        return Ok(None);
     }
+    partition(ks, name).map(Some)
 }
 
-fn start_worker() -> mpsc::Sender<Action> {
-    let (tx, rx) = mpsc::channel(16);
-    std::thread::spawn(move || {
-        let mut keyspace = None;
-        for action in block_on_stream(rx) {
-            let err = match action {
-                Action::CreateDb {
-                    qualifier,
-                    organization,
-                    application,
-                    resp_tx: resp,
-                } => {
-                    let ks = get_path(&qualifier, &organization, &application)
-                        .and_then(|path| fjall::Config::new(path).open().map_err(Error::Fjall));
-                    let result = match ks {
-                        Err(e) => Err(e),
-                        Ok(ks) => {
-                            keyspace = Some(ks);
-                            Ok(())
-                        }
-                    };
-                    resp.send(result).is_err()
-                }
-                Action::CreateTable {
-                    name,
-                    resp_tx: resp,
-                } => resp
-                    .send(handle_create_table(keyspace.as_mut(), &name))
-                    .is_err(),
-                Action::IsTableEmpty { table, resp_tx } => {
-                    let result = table.is_empty().map_err(Error::Fjall);
-                    resp_tx.send(result).is_err()
-                }
-                Action::FirstKeyValue { table, resp_tx } => {
-                    let result = table.first_key_value().map_err(Error::Fjall);
-                    resp_tx.send(result).is_err()
-                }
-                Action::DeleteTable { table, resp_tx } => {
-                    let result = handle_delete_table(keyspace.as_mut(), table);
-                    resp_tx.send(result).is_err()
-                }
-                Action::Insert {
-                    table,
-                    key,
-                    value,
-                    resp_tx,
-                } => {
-                    let result = table.insert(key, value).map_err(Error::Fjall);
-                    resp_tx.send(result).is_err()
-                }
-                Action::Get {
-                    table,
-                    key,
-                    resp_tx,
-                } => {
-                    let result = table.get(key).map_err(Error::Fjall);
-                    resp_tx.send(result).is_err()
-                }
-                Action::Contains {
-                    table,
-                    key,
-                    resp_tx,
-                } => {
-                    let result = table.contains_key(key).map_err(Error::Fjall);
-                    resp_tx.send(result).is_err()
-                }
-                Action::Len { table, resp_tx } => {
-                    let result = table.len().map_err(Error::Fjall);
-                    resp_tx.send(result).is_err()
-                }
-                Action::Remove {
-                    table,
-                    key,
-                    resp_tx,
-                } => {
-                    let result = table.remove(key).map_err(Error::Fjall);
-                    resp_tx.send(result).is_err()
-                }
-                Action::GetPrefix {
-                    table,
-                    prefix,
-                    resp_tx,
-                } => {
-                    let result = table.prefix(prefix).collect();
-                    resp_tx.send(result).is_err()
-                }
-            };
-            if err {
-                tracing::warn!("Failed to send response for action");
+impl Backend for FjallBackend {
+    fn close(&self) {
+        self.0.close();
+    }
+
+    fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool> {
+        let table = table.to_string();
+        Box::pin(self.run(move |ks| {
+            let new = !ks.partition_exists(&table);
+            partition(ks, &table)?;
+            Ok(new)
+        }))
+    }
+
+    fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()> {
+        let table = table.to_string();
+        Box::pin(self.run(move |ks| {
+            if ks.partition_exists(&table) {
+                ks.delete_partition(partition(ks, &table)?)?;
             }
-        }
-    });
-    tx
-}
+            Ok(())
+        }))
+    }
 
-fn handle_delete_table(ks: Option<&mut Keyspace>, table: PartitionHandle) -> Result<(), Error> {
-    let ks = ks.ok_or(Error::StoreNotInitialized)?;
-    ks.delete_partition(table)?;
-    Ok(())
-}
+    fn list_tables(&self) -> BackendFuture<'_, Vec<String>> {
+        Box::pin(self.run(|ks| {
+            Ok(ks
+                .list_partitions()
+                .iter()
+                .map(|name| name.to_string())
+                .collect())
+        }))
+    }
 
-fn handle_create_table(
-    ks: Option<&mut Keyspace>,
-    name: &str,
-) -> Result<(PartitionHandle, bool), Error> {
-    let ks = ks.ok_or(Error::StoreNotInitialized)?;
-    let new = !ks.partition_exists(name);
-    let items = ks.open_partition(name, PartitionCreateOptions::default())?;
-    Ok((items, new))
-}
+    fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>> {
+        Box::pin(self.read_on(table, None, move |partition| {
+            Ok(partition.get(key)?.map(|value| value.to_vec()))
+        }))
+    }
 
-pub(crate) async fn create_database(
-    qualifier: &str,
-    organization: &str,
-    application: &str,
-) -> Result<Database, Error> {
-    let mut tx = start_worker();
-    let (resp_tx, resp_rx) = oneshot::channel();
-    tx.send(Action::CreateDb {
-        qualifier: qualifier.to_string(),
-        organization: organization.to_string(),
-        application: application.to_string(),
-        resp_tx,
-    })
-    .await?;
-    resp_rx.await??;
-    Ok(Database(tx))
-}
+    fn get_many<'a>(
+        &'a self,
+        table: &'a str,
+        keys: Vec<Vec<u8>>,
+    ) -> BackendFuture<'a, Vec<Option<RawValue>>> {
+        let missing = vec![None; keys.len()];
+        Box::pin(self.read_on(table, missing, move |partition| {
+            keys.into_iter()
+                .map(|key| Ok(partition.get(key)?.map(|value| value.to_vec())))
+                .collect()
+        }))
+    }
 
-pub async fn create_table<'a, K: Key, V: Value>(
-    store: &'a UniStore,
-    name: &str,
-    replace_if_incomatible: bool,
-) -> Result<UniTable<'a, K, V>, crate::Error> {
-    let (mut table, new) = store.db.create_table(name).await?;
-    let empty = new || store.db.is_table_empty(table.clone()).await?;
-    if new || empty {
-        // If the table is new or we are not replacing, return the table
-        return Ok(UniTable {
-            store,
-            name: name.to_string(),
-            table,
-            phantom: std::marker::PhantomData,
-        });
+    fn insert<'a>(
+        &'a self,
+        table: &'a str,
+        key: Vec<u8>,
+        value: RawValue,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(self.run_on(table, move |partition| Ok(partition.insert(key, value)?)))
     }
-    let mut replace = false;
-    if let Some((key, val)) = store.db.first_key_value(table.clone()).await? {
-        // If the table is not empty, check if the types match
-        if let Err(e) = K::from_bytes(&key) {
-            if replace_if_incomatible {
-                // If we are replacing, we can ignore the type mismatch
-                tracing::warn!("Replacing table {} due to key type mismatch: {}", name, e);
-                replace = true;
-            } else {
-                return Err(e);
-            }
-        }
-        if let Err(e) = rmp_serde::from_slice::<V>(&val) {
-            if replace_if_incomatible {
-                // If we are replacing, we can ignore the type mismatch
-                tracing::warn!("Replacing table {} due to value type mismatch: {}", name, e);
-                replace = true;
-            } else {
-                return Err(crate::Error::ValueTypeMismatch(e.to_string()));
-            }
-        }
+
+    fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()> {
+        Box::pin(self.read_on(table, (), move |partition| Ok(partition.remove(key)?)))
     }
-    if replace {
-        store.db.delete_table(table).await?;
-        (table, _) = store.db.create_table(name).await?;
+
+    fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize> {
+        Box::pin(self.read_on(table, 0, |partition| {
+            if partition.is_empty()? {
+                return Ok(0);
+            }
+            Ok(partition.len()?)
+        }))
     }
 
-    // check if the types match
-    Ok(UniTable {
-        store,
-        name: name.to_string(),
-        table,
-        phantom: std::marker::PhantomData,
-    })
-}
+    fn scan<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Entry>> {
+        Box::pin(self.read_on(table, Vec::new(), move |partition| {
+            let items = partition.range(range);
+            let items: Box<dyn Iterator<Item = _>> = if reverse {
+                Box::new(items.rev())
+            } else {
+                Box::new(items)
+            };
+            items
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|item| {
+                    let (key, value) = item?;
+                    Ok((key.to_vec(), value.to_vec()))
+                })
+                .collect()
+        }))
+    }
 
-pub async fn insert<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-    value: impl AsValue<V>,
-) -> Result<(), Error> {
-    let key = key.as_key().as_bytes().into();
-    let value = rmp_serde::to_vec(&value)?;
-    table
-        .store
-        .db
-        .insert(table.table.clone(), key, value.into())
-        .await?;
-    Ok(())
-}
+    fn scan_keys<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Vec<u8>>> {
+        Box::pin(self.read_on(table, Vec::new(), move |partition| {
+            let items = partition.range(range);
+            let items: Box<dyn Iterator<Item = _>> = if reverse {
+                Box::new(items.rev())
+            } else {
+                Box::new(items)
+            };
+            items
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|item| Ok(item?.0.to_vec()))
+                .collect()
+        }))
+    }
 
-pub async fn contains<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-) -> Result<bool, Error> {
-    let key = key.as_key().as_bytes().into();
-    let contains = table.store.db.contains(table.table.clone(), key).await?;
-    Ok(contains)
-}
+    fn fold_raw<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        init: FoldState,
+        mut f: FoldFn,
+    ) -> BackendFuture<'a, FoldState> {
+        let table = table.to_string();
+        Box::pin(async move {
+            self.run(move |ks| {
+                let Some(partition) = existing_partition(ks, &table)? else {
+                    return Ok(Ok(init));
+                };
+                let entries = partition.range(range).map(|item| {
+                    let (key, value) = item.map_err(Error::from)?;
+                    Ok((key.to_vec(), value.to_vec()))
+                });
+                Ok(fold_entries(entries, init, &mut f))
+            })
+            .await?
+        })
+    }
 
-pub async fn get<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-) -> Result<Option<V>, Error> {
-    let key = key.as_key().as_bytes().into();
-    let value = table.store.db.get(table.table.clone(), key).await?;
-    match value {
-        Some(value) => {
-            let value: V = rmp_serde::from_slice(&value)?;
-            Ok(Some(value))
-        }
-        None => Ok(None),
+    fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>> {
+        // Both steps run in one job, so no other operation can interleave.
+        Box::pin(self.read_on(table, None, move |partition| {
+            let entry = if last {
+                partition.last_key_value()?
+            } else {
+                partition.first_key_value()?
+            };
+            let Some((key, value)) = entry else {
+                return Ok(None);
+            };
+            partition.remove(key.clone())?;
+            Ok(Some((key.to_vec(), value.to_vec())))
+        }))
     }
-}
 
-pub async fn remove<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-) -> Result<(), Error> {
-    let key = key.as_key().as_bytes().into();
-    table.store.db.remove(table.table.clone(), key).await
+    fn write(&self, batch: Vec<WriteOp>) -> BackendFuture<'_, ()> {
+        Box::pin(self.run(move |ks| {
+            let mut writes = ks.batch();
+            for op in batch {
+                match op {
+                    WriteOp::Insert { table, key, value } => {
+                        let Some(partition) = existing_partition(ks, &table)? else {
+                            return Err(Error::TableNotFound(table));
+                        };
+                        writes.insert(&partition, key, value);
+                    }
+                    WriteOp::Remove { table, key } => {
+                        if let Some(partition) = existing_partition(ks, &table)? {
+                            writes.remove(&partition, key);
+                        }
+                    }
+                }
+            }
+            Ok(writes.commit()?)
+        }))
+    }
 }
 
-pub async fn len<K: Key, V: Value>(table: &UniTable<'_, K, V>) -> Result<usize, Error> {
-    let empty = table.store.db.is_table_empty(table.table.clone()).await?;
-    if empty {
-        return Ok(0);
-    }
-    table.store.db.len(table.table.clone()).await
+pub fn encode_value<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    Ok(rmp_serde::to_vec(value)?)
 }
 
-pub async fn is_empty<K: Key, V: Value>(table: &UniTable<'_, K, V>) -> Result<bool, Error> {
-    table.store.db.is_table_empty(table.table.clone()).await
+/// Like [`encode_value`], but encodes structs as maps with their field names.
+pub fn encode_named_value<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    Ok(rmp_serde::to_vec_named(value)?)
 }
 
-pub async fn get_prefix<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    prefix: impl AsKey<K>,
-) -> Result<Vec<(K, V)>, crate::Error> {
-    // TODO: use worker thread
-    futures::future::ready(()).await;
-    let prefix = prefix.as_key().as_bytes();
-    let items = table.store.db.prefix(table.table.clone(), prefix).await?;
-    let mapped = items
-        .into_iter()
-        .map(|i| -> Result<(K, V), crate::Error> {
-            let (k, v) = i.map_err(|e| crate::Error::Native(Error::Fjall(e)))?;
-            let key = K::from_bytes(&k)?;
-            let value = rmp_serde::from_slice::<V>(&v)
-                .map_err(|e| crate::Error::Native(Error::RmpDecode(e)))?;
-            Ok((key, value))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(mapped)
+pub fn decode_value<V: Value>(value: &[u8]) -> Result<V, Error> {
+    Ok(rmp_serde::from_slice(value)?)
 }
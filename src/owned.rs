@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use crate::{Error, Key, UniStore, UniTable, Value};
+
+/// A table that owns a reference to its store instead of borrowing it, so it can be kept
+/// in structs and moved into spawned tasks without leaking the store into a static.
+/// Created by [`UniStore::create_owned_table`], cloning it is cheap.
+///
+/// Indexes and queries of an owned table work like on a borrowed one,
+/// and [`UniIndex`](crate::UniIndex)es created from it keep the store alive as well.
+pub type OwnedUniTable<K, V> = UniTable<'static, K, V, Arc<UniStore>>;
+
+impl UniStore {
+    /// Like [`Self::create_table`], but returns a table that keeps the store alive.
+    pub async fn create_owned_table<K: Key, V: Value>(
+        self: &Arc<Self>,
+        name: &str,
+        replace_if_incompatible: bool,
+    ) -> Result<OwnedUniTable<K, V>, Error> {
+        UniTable::open(self.clone(), name, replace_if_incompatible).await
+    }
+}
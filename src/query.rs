@@ -0,0 +1,315 @@
+use std::{
+    cmp::Ordering,
+    ops::{Bound, RangeBounds},
+};
+
+use futures::future::LocalBoxFuture;
+
+use crate::backend::in_range;
+use crate::{AsKey, Error, Key, StoreRef, UniIndex, UniStore, UniStoreItem, UniTable, Value};
+
+type Lookup<'a, K, V> = Box<dyn FnOnce() -> LocalBoxFuture<'a, Result<Vec<(K, V)>, Error>> + 'a>;
+type Filter<'a, V> = Box<dyn Fn(&V) -> bool + 'a>;
+type Compare<'a, V> = Box<dyn Fn(&V, &V) -> Ordering + 'a>;
+/// Name of an index and a function converting a field value to its index key.
+type FieldIndex<T> = (&'static str, fn(&T) -> String);
+
+/// Builder for queries on a [`UniTable`], created by [`UniTable::query`].
+///
+/// The candidates are loaded from an index lookup if one is set, otherwise the key range
+/// of the table is scanned. Filters, sorting and paging are applied to the loaded values.
+pub struct Query<'a, K: Key, V: Value, S: StoreRef = &'a UniStore> {
+    table: &'a UniTable<'a, K, V, S>,
+    range: (Bound<K>, Bound<K>),
+    prefix: Option<K>,
+    lookup: Option<Lookup<'a, K, V>>,
+    filters: Vec<Filter<'a, V>>,
+    sort: Option<Compare<'a, V>>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl<K: Key, V: Value, S: StoreRef> std::fmt::Debug for Query<'_, K, V, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Query")
+            .field("table", &self.table.name)
+            .field("index", &self.lookup.is_some())
+            .field("filters", &self.filters.len())
+            .field("offset", &self.offset)
+            .field("limit", &self.limit)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, K: Key + 'a, V: Value + 'a, S: StoreRef> Query<'a, K, V, S> {
+    /// Restricts the query to keys inside `range`, in the key order of the backend,
+    /// see [`Key`](crate::Key).
+    pub fn range(mut self, range: impl RangeBounds<K>) -> Self {
+        self.range = (range.start_bound().cloned(), range.end_bound().cloned());
+        self
+    }
+
+    /// Restricts the query to keys starting with `prefix`.
+    pub fn prefix(mut self, prefix: impl AsKey<K>) -> Self {
+        self.prefix = Some(prefix.as_key());
+        self
+    }
+
+    /// Loads the candidates from `index` instead of scanning the table.
+    pub fn index<I: Key + 'a>(
+        mut self,
+        index: &'a UniIndex<'a, I, K, V, S>,
+        value: impl AsKey<I>,
+    ) -> Self {
+        let value = value.as_key();
+        self.lookup = Some(Box::new(move || Box::pin(index.get(value))));
+        self
+    }
+
+    /// Only keeps values for which `filter` returns `true`.
+    /// Multiple filters are combined with a logical AND.
+    pub fn filter(mut self, filter: impl Fn(&V) -> bool + 'a) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Sorts the results with a comparator function.
+    pub fn sort_by(mut self, compare: impl Fn(&V, &V) -> Ordering + 'a) -> Self {
+        self.sort = Some(Box::new(compare));
+        self
+    }
+
+    /// Sorts the results by the field returned from `key`.
+    pub fn sort_by_key<T: Ord>(self, key: impl Fn(&V) -> T + 'a) -> Self {
+        self.sort_by(move |a, b| key(a).cmp(&key(b)))
+    }
+
+    /// Skips the first `offset` results.
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Returns at most `limit` results.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn in_key_range(&self, key: &K) -> bool {
+        let bytes = self.table.encode_key(key.clone());
+        let range = self.table.encode_range(self.range.clone());
+        let prefix = self
+            .prefix
+            .as_ref()
+            .is_none_or(|prefix| bytes.starts_with(&self.table.encode_key(prefix.clone())));
+        in_range(&range, &bytes) && prefix
+    }
+
+    /// Runs the query and returns the matching entries.
+    pub async fn execute(mut self) -> Result<Vec<(K, V)>, Error> {
+        let candidates = match self.lookup.take() {
+            Some(lookup) => lookup()
+                .await?
+                .into_iter()
+                .filter(|(key, _)| self.in_key_range(key))
+                .collect(),
+            None => match &self.prefix {
+                Some(prefix) => {
+                    let items = self.table.get_prefix(prefix.clone()).await?;
+                    items
+                        .into_iter()
+                        .filter(|(key, _)| self.in_key_range(key))
+                        .collect()
+                }
+                None => self.table.get_range(self.range.clone()).await?,
+            },
+        };
+        let mut results = candidates
+            .into_iter()
+            .filter(|(_, value)| self.filters.iter().all(|filter| filter(value)))
+            .collect::<Vec<_>>();
+        if let Some(compare) = &self.sort {
+            results.sort_by(|(_, a), (_, b)| compare(a, b));
+        }
+        let results = results
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(results)
+    }
+}
+
+impl<K: Key, V: Value, S: StoreRef> UniTable<'_, K, V, S> {
+    /// Starts building a query on this table.
+    pub fn query(&self) -> Query<'_, K, V, S> {
+        Query {
+            table: self,
+            range: (Bound::Unbounded, Bound::Unbounded),
+            prefix: None,
+            lookup: None,
+            filters: Vec::new(),
+            sort: None,
+            offset: 0,
+            limit: None,
+        }
+    }
+}
+
+/// Untyped state of the query structs generated by the `UniStoreItem` derive.
+///
+/// An equality filter on an indexed field is used for the lookup,
+/// all other filters are evaluated on the decoded values.
+pub struct ItemQuery<T: UniStoreItem> {
+    lookup: Option<(&'static str, String)>,
+    filters: Vec<Filter<'static, T>>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl<T: UniStoreItem> Default for ItemQuery<T> {
+    fn default() -> Self {
+        Self {
+            lookup: None,
+            filters: Vec::new(),
+            offset: 0,
+            limit: None,
+        }
+    }
+}
+
+impl<T: UniStoreItem> std::fmt::Debug for ItemQuery<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ItemQuery")
+            .field("lookup", &self.lookup)
+            .field("filters", &self.filters.len())
+            .field("offset", &self.offset)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<T: UniStoreItem> ItemQuery<T> {
+    pub fn filter(&mut self, filter: impl Fn(&T) -> bool + 'static) {
+        self.filters.push(Box::new(filter));
+    }
+
+    pub fn offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    pub fn limit(&mut self, limit: usize) {
+        self.limit = Some(limit);
+    }
+
+    pub async fn execute(self) -> Result<Vec<(T::Key, T)>, Error> {
+        let table = T::table().await;
+        let mut query = match self.lookup {
+            Some((index, value)) => {
+                let index = T::index_table(index).await?;
+                table.query().index(index, value)
+            }
+            None => table.query(),
+        };
+        for filter in self.filters {
+            query = query.filter(filter);
+        }
+        query = query.offset(self.offset);
+        if let Some(limit) = self.limit {
+            query = query.limit(limit);
+        }
+        query.execute().await
+    }
+}
+
+/// Implemented by the query structs generated by the `UniStoreItem` derive.
+pub trait TypedQuery: Sized {
+    type Item: UniStoreItem;
+
+    fn item_query(&mut self) -> &mut ItemQuery<Self::Item>;
+}
+
+/// A field selected in a generated query, completed by one of the comparison methods.
+pub struct FieldFilter<Q: TypedQuery, T> {
+    query: Q,
+    get: fn(&Q::Item) -> &T,
+    index: Option<FieldIndex<T>>,
+}
+
+impl<Q: TypedQuery, T> std::fmt::Debug for FieldFilter<Q, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldFilter")
+            .field("index", &self.index.map(|(index, _)| index))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Q: TypedQuery, T: 'static> FieldFilter<Q, T> {
+    /// Creates a filter on the field returned by `get`.
+    /// `index` is the name of the index on the field and a function to convert a value to its index key.
+    pub fn new(query: Q, get: fn(&Q::Item) -> &T, index: Option<FieldIndex<T>>) -> Self {
+        Self { query, get, index }
+    }
+
+    fn with(mut self, predicate: impl Fn(&T) -> bool + 'static) -> Q {
+        let get = self.get;
+        self.query
+            .item_query()
+            .filter(move |item| predicate(get(item)));
+        self.query
+    }
+
+    pub fn eq(mut self, value: T) -> Q
+    where
+        T: PartialEq,
+    {
+        if let Some((index, to_key)) = self.index {
+            let query = self.query.item_query();
+            if query.lookup.is_none() {
+                query.lookup = Some((index, to_key(&value)));
+            }
+        }
+        self.with(move |field| *field == value)
+    }
+
+    pub fn ne(self, value: T) -> Q
+    where
+        T: PartialEq,
+    {
+        self.with(move |field| *field != value)
+    }
+
+    pub fn gt(self, value: T) -> Q
+    where
+        T: PartialOrd,
+    {
+        self.with(move |field| *field > value)
+    }
+
+    pub fn ge(self, value: T) -> Q
+    where
+        T: PartialOrd,
+    {
+        self.with(move |field| *field >= value)
+    }
+
+    pub fn lt(self, value: T) -> Q
+    where
+        T: PartialOrd,
+    {
+        self.with(move |field| *field < value)
+    }
+
+    pub fn le(self, value: T) -> Q
+    where
+        T: PartialOrd,
+    {
+        self.with(move |field| *field <= value)
+    }
+
+    /// Keeps items whose field matches a custom predicate.
+    pub fn matches(self, predicate: impl Fn(&T) -> bool + 'static) -> Q {
+        self.with(predicate)
+    }
+}
@@ -0,0 +1,69 @@
+use std::ops::RangeBounds;
+
+use serde::Serialize;
+
+use crate::{AsKey, Error, Key, StoreRef, UniTable, Value};
+
+/// A value in the exact representation stored by the backend:
+/// msgpack bytes natively, the JS value on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+pub type RawValue = Vec<u8>;
+/// A value in the exact representation stored by the backend:
+/// msgpack bytes natively, the JS value on wasm.
+#[cfg(target_arch = "wasm32")]
+pub type RawValue = wasm_bindgen::JsValue;
+
+/// Encodes `value` into the representation stored by the backend.
+pub fn encode_raw<V: Value>(value: &V) -> Result<RawValue, Error> {
+    encode(value, false)
+}
+
+/// Encodes `value`, with the field names of structs if `named`, see [`Backend::named_values`].
+///
+/// [`Backend::named_values`]: crate::Backend::named_values
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+pub(crate) fn encode<T: Serialize + ?Sized>(value: &T, named: bool) -> Result<RawValue, Error> {
+    #[cfg(target_arch = "wasm32")]
+    let raw = crate::wasm::encode_value(value)?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let raw = match named {
+        true => crate::native::encode_named_value(value)?,
+        false => crate::native::encode_value(value)?,
+    };
+    Ok(raw)
+}
+
+/// Decodes a raw value returned by [`UniTable::get_raw`] or [`UniTable::scan_raw`].
+pub fn decode_raw<V: Value>(raw: RawValue) -> Result<V, Error> {
+    #[cfg(target_arch = "wasm32")]
+    let value = crate::wasm::decode_value(raw)?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let value = crate::native::decode_value(&raw)?;
+    Ok(value)
+}
+
+impl<K: Key, V: Value, S: StoreRef> UniTable<'_, K, V, S> {
+    /// Returns the stored representation of the value for `key` without decoding it.
+    pub async fn get_raw(&self, key: impl AsKey<K>) -> Result<Option<RawValue>, Error> {
+        let key = self.encode_key(key.as_key());
+        self.backend().get(&self.name, key).await
+    }
+
+    /// Stores an already encoded value.
+    /// The value is not checked, so it should come from [`encode_raw`] or [`UniTable::get_raw`]
+    /// of a table with the same value type.
+    pub async fn insert_raw(&self, key: impl AsKey<K>, value: RawValue) -> Result<(), Error> {
+        let key = self.encode_key(key.as_key());
+        self.backend().insert(&self.name, key, value).await
+    }
+
+    /// Returns the entries with keys inside `range` with their values in stored representation.
+    pub async fn scan_raw(&self, range: impl RangeBounds<K>) -> Result<Vec<(K, RawValue)>, Error> {
+        let range = self.encode_range(range);
+        let entries = self.backend().scan(&self.name, range, false, None).await?;
+        entries
+            .into_iter()
+            .map(|(key, value)| Ok((self.decode_key(&key)?, value)))
+            .collect()
+    }
+}
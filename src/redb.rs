@@ -0,0 +1,286 @@
+use std::ops::Bound;
+
+use redb::{
+    Database, ReadOnlyTable, ReadableTableMetadata, TableDefinition, TableError, TableHandle,
+};
+
+use crate::backend::{
+    Backend, BackendFuture, Entry, FoldFn, FoldState, KeyBounds, WriteOp, fold_entries,
+    is_empty_range,
+};
+use crate::native::{Error, Worker};
+use crate::{RawValue, UniStoreBuilder};
+
+type Definition<'a> = TableDefinition<'a, &'static [u8], &'static [u8]>;
+type Table<'txn> = redb::Table<'txn, &'static [u8], &'static [u8]>;
+
+macro_rules! impl_from_redb {
+    ($($error:ty),*) => {
+        $(impl From<$error> for Error {
+            fn from(e: $error) -> Self {
+                Error::Redb(Box::new(e.into()))
+            }
+        })*
+    };
+}
+
+impl_from_redb!(
+    redb::Error,
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
+
+/// The redb backend. All tables are stored in a single file without background threads,
+/// each table of the store is a redb table with byte keys and values.
+///
+/// Runs on a [`Worker`] that owns the database.
+pub struct RedbBackend(Worker<Database>);
+
+impl RedbBackend {
+    pub(crate) async fn open(builder: &UniStoreBuilder) -> Result<Self, Error> {
+        let worker = Worker::spawn(builder, "unistore.redb", |path| {
+            if let Some(dir) = std::path::Path::new(path).parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            Ok(Database::create(path)?)
+        })
+        .await?;
+        Ok(RedbBackend(worker))
+    }
+
+    /// Runs `f` on the worker thread with the table, or returns `default` if it does not exist.
+    async fn read<R: Send + 'static>(
+        &self,
+        table: &str,
+        default: R,
+        f: impl FnOnce(ReadOnlyTable<&'static [u8], &'static [u8]>) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        let table = table.to_string();
+        self.0
+            .run(move |db| {
+                let txn = db.begin_read()?;
+                match txn.open_table(Definition::new(&table)) {
+                    Ok(table) => f(table),
+                    Err(TableError::TableDoesNotExist(_)) => Ok(default),
+                    Err(e) => Err(e.into()),
+                }
+            })
+            .await
+    }
+
+    /// Runs `f` in a write transaction that is committed if `f` succeeds.
+    async fn write_txn<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&redb::WriteTransaction) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        self.0
+            .run(move |db| {
+                let txn = db.begin_write()?;
+                let result = f(&txn)?;
+                txn.commit()?;
+                Ok(result)
+            })
+            .await
+    }
+}
+
+/// Opens `table` in a write transaction, or returns `None` if it does not exist,
+/// as [`redb::WriteTransaction::open_table`] would create it.
+fn existing_table<'txn>(
+    txn: &'txn redb::WriteTransaction,
+    table: &str,
+) -> Result<Option<Table<'txn>>, Error> {
+    if !txn.list_tables()?.any(|handle| handle.name() == table) {
+        return Ok(None);
+    }
+    Ok(Some(txn.open_table(Definition::new(table))?))
+}
+
+fn as_slices(range: &KeyBounds) -> (Bound<&[u8]>, Bound<&[u8]>) {
+    (
+        range.0.as_ref().map(Vec::as_slice),
+        range.1.as_ref().map(Vec::as_slice),
+    )
+}
+
+impl Backend for RedbBackend {
+    fn close(&self) {
+        self.0.close();
+    }
+
+    fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool> {
+        let table = table.to_string();
+        Box::pin(self.write_txn(move |txn| {
+            let new = !txn.list_tables()?.any(|handle| handle.name() == table);
+            txn.open_table(Definition::new(&table))?;
+            Ok(new)
+        }))
+    }
+
+    fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()> {
+        let table = table.to_string();
+        Box::pin(self.write_txn(move |txn| {
+            txn.delete_table(Definition::new(&table))?;
+            Ok(())
+        }))
+    }
+
+    fn list_tables(&self) -> BackendFuture<'_, Vec<String>> {
+        Box::pin(self.0.run(|db| {
+            let txn = db.begin_read()?;
+            let tables = txn.list_tables()?;
+            Ok(tables.map(|handle| handle.name().to_string()).collect())
+        }))
+    }
+
+    fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>> {
+        Box::pin(self.read(table, None, move |table| {
+            Ok(table
+                .get(key.as_slice())?
+                .map(|value| value.value().to_vec()))
+        }))
+    }
+
+    fn get_many<'a>(
+        &'a self,
+        table: &'a str,
+        keys: Vec<Vec<u8>>,
+    ) -> BackendFuture<'a, Vec<Option<RawValue>>> {
+        let missing = vec![None; keys.len()];
+        Box::pin(self.read(table, missing, move |table| {
+            keys.iter()
+                .map(|key| {
+                    Ok(table
+                        .get(key.as_slice())?
+                        .map(|value| value.value().to_vec()))
+                })
+                .collect()
+        }))
+    }
+
+    fn insert<'a>(
+        &'a self,
+        table: &'a str,
+        key: Vec<u8>,
+        value: RawValue,
+    ) -> BackendFuture<'a, ()> {
+        let table = table.to_string();
+        Box::pin(self.write_txn(move |txn| {
+            let Some(mut table) = existing_table(txn, &table)? else {
+                return Err(Error::TableNotFound(table));
+            };
+            table.insert(key.as_slice(), value.as_slice())?;
+            Ok(())
+        }))
+    }
+
+    fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()> {
+        let table = table.to_string();
+        Box::pin(self.write_txn(move |txn| {
+            if let Some(mut table) = existing_table(txn, &table)? {
+                table.remove(key.as_slice())?;
+            }
+            Ok(())
+        }))
+    }
+
+    fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize> {
+        Box::pin(self.read(table, 0, |table| Ok(table.len()? as usize)))
+    }
+
+    fn scan<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Entry>> {
+        Box::pin(self.read(table, Vec::new(), move |table| {
+            if is_empty_range(&range) {
+                return Ok(Vec::new());
+            }
+            let items = table.range::<&[u8]>(as_slices(&range))?;
+            let items: Box<dyn Iterator<Item = _>> = if reverse {
+                Box::new(items.rev())
+            } else {
+                Box::new(items)
+            };
+            items
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|item| {
+                    let (key, value) = item?;
+                    Ok((key.value().to_vec(), value.value().to_vec()))
+                })
+                .collect()
+        }))
+    }
+
+    fn fold_raw<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        init: FoldState,
+        mut f: FoldFn,
+    ) -> BackendFuture<'a, FoldState> {
+        let table = table.to_string();
+        Box::pin(async move {
+            self.0
+                .run(move |db| {
+                    let txn = db.begin_read()?;
+                    let table = match txn.open_table(Definition::new(&table)) {
+                        Ok(table) => table,
+                        Err(TableError::TableDoesNotExist(_)) => return Ok(Ok(init)),
+                        Err(e) => return Err(e.into()),
+                    };
+                    if is_empty_range(&range) {
+                        return Ok(Ok(init));
+                    }
+                    let entries = table.range::<&[u8]>(as_slices(&range))?.map(|item| {
+                        let (key, value) = item.map_err(Error::from)?;
+                        Ok((key.value().to_vec(), value.value().to_vec()))
+                    });
+                    Ok(fold_entries(entries, init, &mut f))
+                })
+                .await?
+        })
+    }
+
+    fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>> {
+        let table = table.to_string();
+        Box::pin(self.write_txn(move |txn| {
+            let Some(mut table) = existing_table(txn, &table)? else {
+                return Ok(None);
+            };
+            let entry = if last {
+                table.pop_last()?
+            } else {
+                table.pop_first()?
+            };
+            Ok(entry.map(|(key, value)| (key.value().to_vec(), value.value().to_vec())))
+        }))
+    }
+
+    fn write(&self, batch: Vec<WriteOp>) -> BackendFuture<'_, ()> {
+        Box::pin(self.write_txn(move |txn| {
+            for op in batch {
+                match op {
+                    WriteOp::Insert { table, key, value } => {
+                        let Some(mut table) = existing_table(txn, &table)? else {
+                            return Err(Error::TableNotFound(table));
+                        };
+                        table.insert(key.as_slice(), value.as_slice())?;
+                    }
+                    WriteOp::Remove { table, key } => {
+                        if let Some(mut table) = existing_table(txn, &table)? {
+                            table.remove(key.as_slice())?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }))
+    }
+}
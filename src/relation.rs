@@ -0,0 +1,68 @@
+use std::any::TypeId;
+
+use crate::Error;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type RelationFuture = futures::future::BoxFuture<'static, Result<(), Error>>;
+#[cfg(target_arch = "wasm32")]
+pub type RelationFuture = futures::future::LocalBoxFuture<'static, Result<(), Error>>;
+
+/// What happens to referencing items when the referenced item is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDelete {
+    /// Remove all referencing items.
+    Cascade,
+    /// Refuse to remove the referenced item while it is referenced.
+    Restrict,
+    /// Set the reference of all referencing items to `None`.
+    SetNull,
+}
+
+/// A reference from a field of `child` to the key of another item.
+///
+/// Relations are registered by the `UniStoreItem` derive for fields marked with
+/// `#[unistore(references = ...)]` and looked up when a referenced item is removed.
+pub struct Relation {
+    pub parent: fn() -> TypeId,
+    pub child: &'static str,
+    pub on_delete: OnDelete,
+    /// Checks without modifying anything that the policy can be applied to all children
+    /// referencing the given parent key string, including the items removed by cascades.
+    pub check: fn(String) -> RelationFuture,
+    /// Applies the policy to all children referencing the given parent key string.
+    pub apply: fn(String) -> RelationFuture,
+}
+
+inventory::collect!(Relation);
+
+fn relations<P: 'static>() -> impl Iterator<Item = &'static Relation> {
+    inventory::iter::<Relation>
+        .into_iter()
+        .filter(|relation| (relation.parent)() == TypeId::of::<P>())
+}
+
+/// Checks the `on_delete` policies of all relations referencing `P`, following cascades
+/// through the whole tree of items that would be removed.
+pub(crate) async fn check_delete<P: 'static>(key: String) -> Result<(), Error> {
+    for relation in relations::<P>() {
+        (relation.check)(key.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Applies the `on_delete` policies of all relations referencing `P`.
+/// Restrictions are checked first for every item that would be removed by a cascade,
+/// so nothing is modified if any of them fails.
+pub(crate) async fn on_delete<P: 'static>(key: String) -> Result<(), Error> {
+    check_delete::<P>(key.clone()).await?;
+    apply_delete::<P>(key).await
+}
+
+/// Applies the `on_delete` policies of all relations referencing `P` without checking them first,
+/// for keys that already passed [`check_delete`].
+pub(crate) async fn apply_delete<P: 'static>(key: String) -> Result<(), Error> {
+    for relation in relations::<P>() {
+        (relation.apply)(key.clone()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use std::ops::Bound;
+
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+
+use crate::backend::{
+    Backend, BackendFuture, Entry, FoldFn, FoldState, KeyBounds, WriteOp, fold_entries,
+};
+use crate::native::{Error, Worker};
+use crate::{RawValue, UniStoreBuilder};
+
+/// The SQLite backend. Each table of the store is a SQLite table with a BLOB key and a BLOB value,
+/// so the database file can be inspected with other tools. BLOBs are compared bytewise,
+/// which gives the same key order as the other backends.
+///
+/// Runs on a [`Worker`] that owns the connection.
+pub struct SqliteBackend(Worker<Connection>);
+
+impl SqliteBackend {
+    pub(crate) async fn open(builder: &UniStoreBuilder) -> Result<Self, Error> {
+        let worker = Worker::spawn(builder, "unistore.sqlite", |path| {
+            if let Some(dir) = std::path::Path::new(path).parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let connection = Connection::open(path)?;
+            connection.pragma_update(None, "journal_mode", "WAL")?;
+            Ok(connection)
+        })
+        .await?;
+        Ok(SqliteBackend(worker))
+    }
+
+    /// Runs `f` on the worker thread, or returns `default` if the table does not exist.
+    async fn read<R: Send + 'static>(
+        &self,
+        table: &str,
+        default: R,
+        f: impl FnOnce(&Connection) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, crate::Error> {
+        let table = table.to_string();
+        self.0
+            .run(move |connection| match table_exists(connection, &table)? {
+                true => f(connection),
+                false => Ok(default),
+            })
+            .await
+    }
+}
+
+fn table_exists(connection: &Connection, table: &str) -> Result<bool, rusqlite::Error> {
+    connection
+        .prepare_cached(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        )?
+        .query_row([table], |row| row.get(0))
+}
+
+/// Quotes a table name for use as an SQL identifier.
+fn quote(table: &str) -> String {
+    format!("\"{}\"", table.replace('"', "\"\""))
+}
+
+/// Returns the `WHERE` condition selecting `range` and its parameters.
+fn range_condition(range: KeyBounds) -> (String, Vec<Vec<u8>>) {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+    let mut bound = |bound: Bound<Vec<u8>>, included: &str, excluded: &str| match bound {
+        Bound::Included(key) => {
+            conditions.push(format!("key {included} ?"));
+            params.push(key);
+        }
+        Bound::Excluded(key) => {
+            conditions.push(format!("key {excluded} ?"));
+            params.push(key);
+        }
+        Bound::Unbounded => {}
+    };
+    bound(range.0, ">=", ">");
+    bound(range.1, "<=", "<");
+    if conditions.is_empty() {
+        return ("1".to_string(), params);
+    }
+    (conditions.join(" AND "), params)
+}
+
+fn first_entry(
+    connection: &Connection,
+    table: &str,
+    last: bool,
+) -> Result<Option<Entry>, rusqlite::Error> {
+    let order = if last { "DESC" } else { "ASC" };
+    let sql = format!(
+        "SELECT key, value FROM {} ORDER BY key {order} LIMIT 1",
+        quote(table)
+    );
+    connection
+        .query_row(&sql, [], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()
+}
+
+impl Backend for SqliteBackend {
+    fn close(&self) {
+        self.0.close();
+    }
+
+    fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool> {
+        let table = table.to_string();
+        Box::pin(self.0.run(move |connection| {
+            let sql = format!(
+                "CREATE TABLE {} \
+                 (key BLOB PRIMARY KEY NOT NULL, value BLOB NOT NULL) WITHOUT ROWID",
+                quote(&table)
+            );
+            let exists = table_exists(connection, &table)?;
+            if !exists {
+                connection.execute(&sql, [])?;
+            }
+            Ok(!exists)
+        }))
+    }
+
+    fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()> {
+        let sql = format!("DROP TABLE IF EXISTS {}", quote(table));
+        Box::pin(self.0.run(move |connection| {
+            connection.execute(&sql, [])?;
+            Ok(())
+        }))
+    }
+
+    fn list_tables(&self) -> BackendFuture<'_, Vec<String>> {
+        Box::pin(self.0.run(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'",
+            )?;
+            let names = statement
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            Ok(names)
+        }))
+    }
+
+    fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>> {
+        let sql = format!("SELECT value FROM {} WHERE key = ?1", quote(table));
+        Box::pin(self.read(table, None, move |connection| {
+            let value = connection
+                .prepare_cached(&sql)?
+                .query_row([key], |row| row.get(0))
+                .optional()?;
+            Ok(value)
+        }))
+    }
+
+    fn get_many<'a>(
+        &'a self,
+        table: &'a str,
+        keys: Vec<Vec<u8>>,
+    ) -> BackendFuture<'a, Vec<Option<RawValue>>> {
+        let sql = format!("SELECT value FROM {} WHERE key = ?1", quote(table));
+        let missing = vec![None; keys.len()];
+        Box::pin(self.read(table, missing, move |connection| {
+            let mut statement = connection.prepare_cached(&sql)?;
+            let values = keys
+                .into_iter()
+                .map(|key| statement.query_row([key], |row| row.get(0)).optional())
+                .collect::<Result<_, _>>()?;
+            Ok(values)
+        }))
+    }
+
+    fn insert<'a>(
+        &'a self,
+        table: &'a str,
+        key: Vec<u8>,
+        value: RawValue,
+    ) -> BackendFuture<'a, ()> {
+        let sql = format!(
+            "INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)",
+            quote(table)
+        );
+        let table = table.to_string();
+        Box::pin(self.0.run(move |connection| {
+            if !table_exists(connection, &table)? {
+                return Err(Error::TableNotFound(table));
+            }
+            connection
+                .prepare_cached(&sql)?
+                .execute(params![key, value])?;
+            Ok(())
+        }))
+    }
+
+    fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()> {
+        let sql = format!("DELETE FROM {} WHERE key = ?1", quote(table));
+        Box::pin(self.read(table, (), move |connection| {
+            connection.prepare_cached(&sql)?.execute([key])?;
+            Ok(())
+        }))
+    }
+
+    fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize> {
+        let sql = format!("SELECT COUNT(*) FROM {}", quote(table));
+        Box::pin(self.read(table, 0, move |connection| {
+            let count: i64 = connection.query_row(&sql, [], |row| row.get(0))?;
+            Ok(count as usize)
+        }))
+    }
+
+    fn scan<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Entry>> {
+        let (condition, params) = range_condition(range);
+        let order = if reverse { "DESC" } else { "ASC" };
+        // A negative limit means no limit in SQLite
+        let limit = limit.map_or(-1, |limit| limit as i64);
+        let sql = format!(
+            "SELECT key, value FROM {} WHERE {condition} ORDER BY key {order} LIMIT {limit}",
+            quote(table)
+        );
+        Box::pin(self.read(table, Vec::new(), move |connection| {
+            let mut statement = connection.prepare_cached(&sql)?;
+            let entries = statement
+                .query_map(params_from_iter(params), |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect::<Result<_, _>>()?;
+            Ok(entries)
+        }))
+    }
+
+    fn scan_keys<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Vec<u8>>> {
+        let (condition, params) = range_condition(range);
+        let order = if reverse { "DESC" } else { "ASC" };
+        let limit = limit.map_or(-1, |limit| limit as i64);
+        let sql = format!(
+            "SELECT key FROM {} WHERE {condition} ORDER BY key {order} LIMIT {limit}",
+            quote(table)
+        );
+        Box::pin(self.read(table, Vec::new(), move |connection| {
+            let mut statement = connection.prepare_cached(&sql)?;
+            let keys = statement
+                .query_map(params_from_iter(params), |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            Ok(keys)
+        }))
+    }
+
+    fn fold_raw<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        init: FoldState,
+        mut f: FoldFn,
+    ) -> BackendFuture<'a, FoldState> {
+        let (condition, params) = range_condition(range);
+        let sql = format!(
+            "SELECT key, value FROM {} WHERE {condition} ORDER BY key",
+            quote(table)
+        );
+        let table = table.to_string();
+        Box::pin(async move {
+            self.0
+                .run(move |connection| {
+                    if !table_exists(connection, &table)? {
+                        return Ok(Ok(init));
+                    }
+                    let mut statement = connection.prepare_cached(&sql)?;
+                    let entries = statement
+                        .query_map(params_from_iter(params), |row| {
+                            Ok((row.get(0)?, row.get(1)?))
+                        })?
+                        .map(|entry| Ok(entry.map_err(Error::from)?));
+                    Ok(fold_entries(entries, init, &mut f))
+                })
+                .await?
+        })
+    }
+
+    fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>> {
+        let table = table.to_string();
+        Box::pin(self.0.run(move |connection| {
+            let transaction = connection.transaction()?;
+            if !table_exists(&transaction, &table)? {
+                return Ok(None);
+            }
+            let entry = first_entry(&transaction, &table, last)?;
+            if let Some((key, _)) = &entry {
+                let sql = format!("DELETE FROM {} WHERE key = ?1", quote(&table));
+                transaction.execute(&sql, [key])?;
+            }
+            transaction.commit()?;
+            Ok(entry)
+        }))
+    }
+
+    fn write(&self, batch: Vec<WriteOp>) -> BackendFuture<'_, ()> {
+        Box::pin(self.0.run(move |connection| {
+            let transaction = connection.transaction()?;
+            for op in batch {
+                match op {
+                    WriteOp::Insert { table, key, value } => {
+                        if !table_exists(&transaction, &table)? {
+                            return Err(Error::TableNotFound(table));
+                        }
+                        let sql = format!(
+                            "INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)",
+                            quote(&table)
+                        );
+                        transaction
+                            .prepare_cached(&sql)?
+                            .execute(params![key, value])?;
+                    }
+                    WriteOp::Remove { table, key } => {
+                        if !table_exists(&transaction, &table)? {
+                            continue;
+                        }
+                        let sql = format!("DELETE FROM {} WHERE key = ?1", quote(&table));
+                        transaction.prepare_cached(&sql)?.execute([key])?;
+                    }
+                }
+            }
+            transaction.commit()?;
+            Ok(())
+        }))
+    }
+}
@@ -27,17 +27,39 @@ fn initialize() {
     });
 }
 
+/// Builder of the store shared by the tests. Natively the tests use an in-memory store by
+/// default, the `UNISTORE_TEST_BACKEND` environment variable runs them against a temporary store
+/// of another backend: `fjall`, `sqlite`, `redb` or `json-file`. On wasm they use IndexedDB.
+fn test_builder() -> crate::UniStoreBuilder {
+    let builder = crate::UniStore::builder();
+    #[cfg(not(target_arch = "wasm32"))]
+    return match std::env::var("UNISTORE_TEST_BACKEND").as_deref() {
+        Err(_) | Ok("" | "memory") => builder.in_memory(),
+        Ok("fjall") => builder.temporary(),
+        #[cfg(feature = "sqlite")]
+        Ok("sqlite") => builder.temporary().sqlite(),
+        #[cfg(feature = "redb")]
+        Ok("redb") => builder.temporary().redb(),
+        #[cfg(feature = "json-file")]
+        Ok("json-file") => builder.temporary().json_file(),
+        Ok(backend) => panic!("Unknown or disabled test backend {backend}"),
+    };
+    #[cfg(target_arch = "wasm32")]
+    builder.app("com", "example", "unistore")
+}
+
+// One store for all test modules, as stores must not share a location
+crate::static_store!(get_test_store, test_builder());
+
 mod derive_tests {
     extern crate self as unistore;
     use super::*;
-    use crate::{UniStoreItem, static_store};
+    use crate::UniStoreItem;
     use serde::{Deserialize, Serialize};
 
-    static_store!(get_test_store, "com", "example", "unistore");
-
     #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug)]
     #[unistore(store = get_test_store)]
-    struct Entry {
+    struct PlainEntry {
         #[unistore(key)]
         key: u32,
         value: String,
@@ -48,12 +70,12 @@ mod derive_tests {
     async fn test_derive() {
         let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
         initialize();
-        let value = Entry {
+        let value = PlainEntry {
             key: 1,
             value: "Hello, UniStore!".to_string(),
         };
         value.save().await.expect("Failed to insert value");
-        let retrieved = Entry::get(1).await.expect("Failed to get value");
+        let retrieved = PlainEntry::get(1).await.expect("Failed to get value");
         assert_eq!(retrieved, Some(value));
     }
 }
@@ -61,11 +83,9 @@ mod derive_tests {
 mod index_tests {
     use super::*;
     extern crate self as unistore;
-    use crate::{UniStoreItem, index::UniIndex, static_store};
+    use crate::{UniStoreItem, index::UniIndex};
     use serde::{Deserialize, Serialize};
 
-    static_store!(get_test_store, "com", "example", "unistore");
-
     #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
     #[unistore(store = get_test_store)]
     struct IndexEntry {
@@ -90,7 +110,7 @@ mod index_tests {
         let index_retrieved = IndexEntry::get_by_index("name", "One")
             .await
             .expect("Failed to get value by index");
-        let name_retrived = IndexEntry::get_by_name("One")
+        let name_retrived = IndexEntry::get_by_name("One".to_string())
             .await
             .expect("Failed to get value by name");
         assert_eq!(index_retrieved, vec![(1, value.clone())]);
@@ -136,12 +156,534 @@ mod index_tests {
     }
 }
 
+mod index_count_tests {
+    use super::*;
+    extern crate self as unistore;
+    use crate::{UniIndex, UniStoreItem};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Job {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(index)]
+        status: String,
+        #[unistore(index)]
+        session: String,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_count_and_remove_by_index() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        Job::remove_by_session("a".to_string())
+            .await
+            .expect("Failed to clean up");
+        Job::remove_by_session("b".to_string())
+            .await
+            .expect("Failed to clean up");
+        for (id, status, session) in [(1, "pending", "a"), (2, "pending", "b"), (3, "done", "a")] {
+            let job = Job {
+                id,
+                status: status.to_string(),
+                session: session.to_string(),
+            };
+            job.save().await.expect("Failed to insert value");
+        }
+        assert_eq!(
+            Job::count_by_status("pending".to_string()).await.unwrap(),
+            2
+        );
+        assert_eq!(Job::count_by_status("pend".to_string()).await.unwrap(), 0);
+
+        let removed = Job::remove_by_session("a".to_string())
+            .await
+            .expect("Failed to remove");
+        assert_eq!(removed, 2);
+        assert_eq!(Job::get(1).await.unwrap(), None);
+        assert_eq!(Job::get(3).await.unwrap(), None);
+        assert_eq!(
+            Job::count_by_status("pending".to_string()).await.unwrap(),
+            1
+        );
+        assert_eq!(Job::count_by_status("done".to_string()).await.unwrap(), 0);
+        assert_eq!(Job::count_by_session("a".to_string()).await.unwrap(), 0);
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_remove_all_cleans_other_indexes() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let store = get_test_store().await;
+        store.drop_table("remove_all_test").await.unwrap();
+        let table = store
+            .create_table::<u32, String>("remove_all_test", false)
+            .await
+            .expect("Failed to create table");
+        let owner: UniIndex<String, _, _> = table.create_index("owner").await.unwrap();
+        let status: UniIndex<String, _, _> = table.create_index("status").await.unwrap();
+        let text = table.create_fulltext_index("text").await.unwrap();
+        for (key, name) in [(1, "ann"), (2, "bob"), (3, "ann")] {
+            table.insert(key, format!("task of {name}")).await.unwrap();
+            owner.insert(name, key).await.unwrap();
+            status.insert("open", key).await.unwrap();
+            text.insert(&format!("task of {name}"), key).await.unwrap();
+        }
+
+        assert_eq!(owner.remove_all("ann").await.unwrap(), 2);
+        assert_eq!(table.keys().await.unwrap(), vec![2]);
+        assert_eq!(owner.count("ann").await.unwrap(), 0);
+        assert_eq!(status.get_keys("open").await.unwrap(), vec![2]);
+        assert_eq!(text.search("task").await.unwrap().len(), 1);
+        assert_eq!(status.index_rev.len().await.unwrap(), 1);
+    }
+}
+
+mod normalized_index_tests {
+    use super::*;
+    extern crate self as unistore;
+    use crate::UniStoreItem;
+    use serde::{Deserialize, Serialize};
+
+    fn trimmed(s: &str) -> String {
+        s.trim().to_string()
+    }
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Person {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(index(normalize = "lowercase"))]
+        email: String,
+        #[unistore(index(normalize = "case_fold"))]
+        street: String,
+        #[unistore(index(normalize = trimmed))]
+        nick: String,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_normalized_index() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let person = Person {
+            id: 1,
+            email: "Jane.Doe@Example.com".to_string(),
+            street: "Hauptstraße".to_string(),
+            nick: " jd ".to_string(),
+        };
+        person.save().await.expect("Failed to insert value");
+        let found = Person::get_by_email("jane.doe@EXAMPLE.com".to_string())
+            .await
+            .expect("Failed to get by email");
+        assert_eq!(found, vec![(1, person.clone())]);
+        let found = Person::get_by_street("HAUPTSTRASSE".to_string())
+            .await
+            .expect("Failed to get by street");
+        assert_eq!(found, vec![(1, person.clone())]);
+        let found = Person::get_by_nick("jd".to_string())
+            .await
+            .expect("Failed to get by nick");
+        assert_eq!(found, vec![(1, person)]);
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    fn test_case_fold_special_cases() {
+        use crate::normalize::case_fold;
+        assert_eq!(case_fold("Straße"), "strasse");
+        assert_eq!(case_fold("STRASSE"), "strasse");
+        assert_eq!(case_fold("ﬁle"), "file");
+        // Not full case folding, see the documentation of `case_fold`
+        assert_eq!(case_fold("ẞ"), "ß");
+        assert_eq!(case_fold("ς"), "σ");
+        assert_eq!(case_fold("ΣΑΣ"), "σας");
+        assert_eq!(case_fold("σασ"), "σας");
+    }
+}
+
+mod computed_index_tests {
+    use super::*;
+    extern crate self as unistore;
+    use crate::UniStoreItem;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    #[unistore(index(name = "domain", with = Self::email_domain, normalize = "lowercase"))]
+    #[unistore(index(name = "year", with = Self::year, ty = u16))]
+    struct Account {
+        #[unistore(key)]
+        id: u32,
+        email: String,
+        created: String,
+    }
+
+    impl Account {
+        fn email_domain(&self) -> &str {
+            self.email.split_once('@').map_or("", |(_, domain)| domain)
+        }
+
+        fn year(&self) -> u16 {
+            self.created[..4].parse().unwrap()
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_computed_index() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let account = Account {
+            id: 1,
+            email: "jane@Example.com".to_string(),
+            created: "2024-05-01".to_string(),
+        };
+        account.save().await.expect("Failed to insert value");
+        let found = Account::get_by_domain("example.com".to_string())
+            .await
+            .expect("Failed to get by domain");
+        assert_eq!(found, vec![(1, account.clone())]);
+        let found = Account::get_by_year(2024u16)
+            .await
+            .expect("Failed to get by year");
+        assert_eq!(found, vec![(1, account)]);
+    }
+}
+
+mod fulltext_tests {
+    use super::*;
+    extern crate self as unistore;
+    use crate::UniStoreItem;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Note {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(fulltext)]
+        body: String,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_fulltext_search() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let first = Note {
+            id: 1,
+            body: "Learning Rust programming".to_string(),
+        };
+        let mut second = Note {
+            id: 2,
+            body: "Rust is fast, Python is easy".to_string(),
+        };
+        first.save().await.expect("Failed to insert value");
+        second.save().await.expect("Failed to insert value");
+
+        let found = Note::search_by_body("rust")
+            .await
+            .expect("Failed to search");
+        assert_eq!(found, vec![(1, first.clone()), (2, second.clone())]);
+        let found = Note::search_by_body("RUST prog*")
+            .await
+            .expect("Failed to search");
+        assert_eq!(found, vec![(1, first.clone())]);
+        let found = Note::search_by_body("rust python")
+            .await
+            .expect("Failed to search");
+        assert_eq!(found, vec![(2, second.clone())]);
+
+        second.body = "Only Python now".to_string();
+        second.save().await.expect("Failed to update value");
+        let found = Note::search_by_body("rust")
+            .await
+            .expect("Failed to search");
+        assert_eq!(found, vec![(1, first)]);
+
+        Note::remove(1).await.expect("Failed to remove");
+        let found = Note::search_by_body("learn*")
+            .await
+            .expect("Failed to search");
+        assert!(found.is_empty());
+    }
+}
+
+mod relation_tests {
+    use super::*;
+    extern crate self as unistore;
+    use crate::{Error, UniStoreItem};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Post {
+        #[unistore(key)]
+        id: u32,
+    }
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Comment {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(references = Post, on_delete = "cascade")]
+        post_id: u32,
+    }
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Draft {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(references = Post, on_delete = "set_null")]
+        post_id: Option<u32>,
+    }
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Pin {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(references = Post, on_delete = "restrict")]
+        post_id: u32,
+    }
+
+    // Two references to the same type need distinct loader names
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Entry {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(references = Post, on_delete = "cascade")]
+        post_id: u32,
+        #[unistore(references = Post, on_delete = "set_null", related_name = "quoting_entries")]
+        quote_id: Option<u32>,
+    }
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Reaction {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(references = Comment, on_delete = "restrict")]
+        comment_id: u32,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_references() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        Post { id: 1 }.save().await.expect("Failed to insert post");
+        Post { id: 2 }.save().await.expect("Failed to insert post");
+        let orphan = Comment { id: 1, post_id: 99 };
+        assert!(matches!(
+            orphan.save().await,
+            Err(Error::ForeignKeyViolation(_))
+        ));
+
+        Comment { id: 1, post_id: 1 }.save().await.unwrap();
+        Comment { id: 2, post_id: 2 }.save().await.unwrap();
+        Draft {
+            id: 1,
+            post_id: Some(1),
+        }
+        .save()
+        .await
+        .unwrap();
+        Pin { id: 1, post_id: 1 }.save().await.unwrap();
+
+        assert!(matches!(
+            Post::remove(1).await,
+            Err(Error::ForeignKeyViolation(_))
+        ));
+        assert!(Post::contains(1).await.unwrap());
+        assert!(Comment::contains(1).await.unwrap());
+
+        Pin::remove(1).await.unwrap();
+        Post::remove(1).await.expect("Failed to remove post");
+        assert!(!Comment::contains(1).await.unwrap());
+        assert!(Comment::contains(2).await.unwrap());
+        assert_eq!(
+            Draft::get(1).await.unwrap(),
+            Some(Draft {
+                id: 1,
+                post_id: None
+            })
+        );
+        assert_eq!(Draft::count_by_post_id(1).await.unwrap(), 0);
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_related_names() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let posts = [Post { id: 40 }, Post { id: 41 }];
+        for post in &posts {
+            post.save().await.expect("Failed to insert post");
+        }
+        let entry = Entry {
+            id: 40,
+            post_id: 40,
+            quote_id: Some(41),
+        };
+        entry.save().await.expect("Failed to insert entry");
+
+        assert_eq!(posts[0].entries().await.unwrap(), vec![entry.clone()]);
+        assert!(posts[0].quoting_entries().await.unwrap().is_empty());
+        assert_eq!(
+            posts[1].quoting_entries().await.unwrap(),
+            vec![entry.clone()]
+        );
+        assert_eq!(
+            Post::load_quoting_entries(&posts).await.unwrap(),
+            vec![vec![], vec![entry.clone()]]
+        );
+        assert_eq!(entry.quote().await.unwrap(), Some(posts[1].clone()));
+
+        Post::remove(40).await.expect("Failed to remove post");
+        Post::remove(41).await.expect("Failed to remove post");
+        assert!(!Entry::contains(40).await.unwrap());
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_restrict_below_cascade() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        Post { id: 30 }.save().await.expect("Failed to insert post");
+        for id in [30, 31] {
+            Comment { id, post_id: 30 }.save().await.unwrap();
+        }
+        Reaction {
+            id: 30,
+            comment_id: 31,
+        }
+        .save()
+        .await
+        .unwrap();
+
+        // The reaction restricts removing a comment that the post would cascade to
+        assert!(matches!(
+            Post::remove(30).await,
+            Err(Error::ForeignKeyViolation(_))
+        ));
+        assert!(Post::contains(30).await.unwrap());
+        assert!(Comment::contains(30).await.unwrap());
+        assert!(Comment::contains(31).await.unwrap());
+
+        Reaction::remove(30).await.unwrap();
+        Post::remove(30).await.expect("Failed to remove post");
+        assert!(!Comment::contains(30).await.unwrap());
+        assert!(!Comment::contains(31).await.unwrap());
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_remove_by_index_checks_all_first() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        Post { id: 50 }.save().await.expect("Failed to insert post");
+        for id in [50, 51] {
+            Comment { id, post_id: 50 }.save().await.unwrap();
+        }
+        Reaction {
+            id: 50,
+            comment_id: 51,
+        }
+        .save()
+        .await
+        .unwrap();
+
+        // The reaction on the second comment keeps the first one from being removed as well
+        assert!(matches!(
+            Comment::remove_by_index("post_id", "50").await,
+            Err(Error::ForeignKeyViolation(_))
+        ));
+        assert!(Comment::contains(50).await.unwrap());
+        assert_eq!(Comment::count_by_index("post_id", "50").await.unwrap(), 2);
+
+        Reaction::remove(50).await.unwrap();
+        assert_eq!(Comment::remove_by_index("post_id", "50").await.unwrap(), 2);
+        assert!(!Comment::contains(51).await.unwrap());
+        assert_eq!(Comment::count_by_index("post_id", "50").await.unwrap(), 0);
+        Post::remove(50).await.unwrap();
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_relation_loaders() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let posts = [Post { id: 10 }, Post { id: 11 }, Post { id: 12 }];
+        for post in &posts {
+            post.save().await.expect("Failed to insert post");
+        }
+        let comments = [
+            Comment {
+                id: 10,
+                post_id: 10,
+            },
+            Comment {
+                id: 11,
+                post_id: 10,
+            },
+            Comment {
+                id: 12,
+                post_id: 12,
+            },
+        ];
+        for comment in &comments {
+            comment.save().await.expect("Failed to insert comment");
+        }
+        let draft = Draft {
+            id: 10,
+            post_id: None,
+        };
+        draft.save().await.expect("Failed to insert draft");
+
+        assert_eq!(comments[2].post().await.unwrap(), Some(posts[2].clone()));
+        assert_eq!(draft.post().await.unwrap(), None);
+        assert_eq!(
+            posts[0].comments().await.unwrap(),
+            vec![comments[0].clone(), comments[1].clone()]
+        );
+
+        let loaded = Post::load_comments(&posts).await.unwrap();
+        assert_eq!(
+            loaded,
+            vec![
+                vec![comments[0].clone(), comments[1].clone()],
+                vec![],
+                vec![comments[2].clone()],
+            ]
+        );
+        let loaded = Comment::load_post(&comments).await.unwrap();
+        assert_eq!(
+            loaded,
+            vec![
+                Some(posts[0].clone()),
+                Some(posts[0].clone()),
+                Some(posts[2].clone())
+            ]
+        );
+        let loaded = Draft::load_post(&[draft]).await.unwrap();
+        assert_eq!(loaded, vec![None]);
+    }
+}
+
 mod prefix_test {
     use super::*;
     extern crate self as unistore;
-    use crate::{static_store, static_table};
+    use crate::static_table;
 
-    static_store!(get_test_store, "com", "example", "unistore");
     static_table!(get_table, "prefix_test", String, (), get_test_store);
 
     #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
@@ -172,3 +714,1451 @@ mod prefix_test {
         }
     }
 }
+
+mod get_many_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::static_table;
+
+    static_table!(get_table, "get_many_test", u32, String, get_test_store);
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_get_many() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_table().await;
+        table.insert(1, "one").await.expect("Failed to insert");
+        table.insert(3, "three").await.expect("Failed to insert");
+        table.remove(2).await.expect("Failed to remove");
+        let values = table.get_many([3, 2, 1]).await.expect("Failed to get many");
+        assert_eq!(
+            values,
+            vec![Some("three".to_string()), None, Some("one".to_string())]
+        );
+    }
+}
+
+mod pop_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::static_table;
+
+    static_table!(get_table, "pop_test", u32, String, get_test_store);
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_pop() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_table().await;
+        while table.pop_first().await.expect("Failed to pop").is_some() {}
+        assert_eq!(table.first().await.unwrap(), None);
+        table.insert(2, "two").await.expect("Failed to insert");
+        table.insert(1, "one").await.expect("Failed to insert");
+        table.insert(3, "three").await.expect("Failed to insert");
+        assert_eq!(table.first().await.unwrap(), Some((1, "one".to_string())));
+        assert_eq!(table.last().await.unwrap(), Some((3, "three".to_string())));
+        assert_eq!(
+            table.pop_first().await.unwrap(),
+            Some((1, "one".to_string()))
+        );
+        assert_eq!(
+            table.pop_last().await.unwrap(),
+            Some((3, "three".to_string()))
+        );
+        assert_eq!(table.len().await.unwrap(), 1);
+        assert_eq!(
+            table.pop_last().await.unwrap(),
+            Some((2, "two".to_string()))
+        );
+        assert_eq!(table.pop_last().await.unwrap(), None);
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_pop_indexed() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_test_store()
+            .await
+            .create_table::<u32, String>("pop_indexed_test", false)
+            .await
+            .expect("Failed to create table");
+        let index: crate::UniIndex<String, _, _> = table
+            .create_index("kind")
+            .await
+            .expect("Failed to create index");
+        table.clear().await.expect("Failed to clear");
+        for key in 1..=3 {
+            table.insert(key, key.to_string()).await.unwrap();
+            index.insert("item", key).await.unwrap();
+        }
+
+        assert_eq!(table.pop_first().await.unwrap(), Some((1, "1".to_string())));
+        assert_eq!(table.pop_last().await.unwrap(), Some((3, "3".to_string())));
+        assert_eq!(index.get_keys("item").await.unwrap(), vec![2]);
+        assert_eq!(index.count("item").await.unwrap(), 1);
+    }
+}
+
+mod remove_range_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::UniIndex;
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_remove_range() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_test_store()
+            .await
+            .create_table::<String, String>("remove_range_test", false)
+            .await
+            .expect("Failed to create table");
+        let index: UniIndex<String, _, _> = table
+            .create_index("kind")
+            .await
+            .expect("Failed to create index");
+        let fulltext = table
+            .create_fulltext_index("text")
+            .await
+            .expect("Failed to create index");
+        table.clear().await.expect("Failed to clear");
+        for key in ["a1", "a2", "b1", "b2", "c1"] {
+            table.insert(key, key).await.unwrap();
+            index.insert("item", key).await.unwrap();
+            fulltext.insert(&format!("word {key}"), key).await.unwrap();
+        }
+
+        assert_eq!(table.remove_prefix("a").await.unwrap(), 2);
+        assert_eq!(index.count("item").await.unwrap(), 3);
+        assert!(fulltext.search("a1").await.unwrap().is_empty());
+        let removed = table
+            .remove_range("b2".to_string()..)
+            .await
+            .expect("Failed to remove range");
+        assert_eq!(removed, 2);
+        assert_eq!(
+            index.get_keys("item").await.unwrap(),
+            vec!["b1".to_string()]
+        );
+        assert_eq!(fulltext.search("word").await.unwrap().len(), 1);
+
+        // Empty and inverted ranges match nothing on every backend
+        let (b1, c1) = ("b1".to_string(), "c1".to_string());
+        assert_eq!(table.remove_range(c1.clone()..b1.clone()).await.unwrap(), 0);
+        assert_eq!(table.remove_range(b1.clone()..b1.clone()).await.unwrap(), 0);
+        assert!(
+            table
+                .get_range(c1.clone()..=b1.clone())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            table
+                .keys_range(b1.clone()..b1.clone())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(table.len().await.unwrap(), 1);
+
+        assert_eq!(table.clear().await.unwrap(), 1);
+        assert!(table.is_empty().await.unwrap());
+        assert!(index.index.is_empty().await.unwrap());
+        assert!(index.index_rev.is_empty().await.unwrap());
+        assert!(fulltext.index.is_empty().await.unwrap());
+        assert!(fulltext.index_rev.is_empty().await.unwrap());
+    }
+}
+
+mod keys_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::static_table;
+
+    static_table!(get_table, "keys_test", String, u32, get_test_store);
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_keys() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_table().await;
+        table.clear().await.expect("Failed to clear");
+        for (i, key) in ["user:2", "user:1", "group:1"].into_iter().enumerate() {
+            table.insert(key, i as u32).await.expect("Failed to insert");
+        }
+        let keys = table.keys().await.expect("Failed to get keys");
+        assert_eq!(keys, vec!["group:1", "user:1", "user:2"]);
+        let keys = table.keys_prefix("user:").await.unwrap();
+        assert_eq!(keys, vec!["user:1", "user:2"]);
+        let keys = table.keys_range(.."user:2".to_string()).await.unwrap();
+        assert_eq!(keys, vec!["group:1", "user:1"]);
+    }
+}
+
+mod key_order_test {
+    use super::*;
+
+    // See the documentation of `Key` for the two orders
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_key_order() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let store = get_test_store().await;
+        let string_keys = store.backend.string_keys();
+        store.drop_table("key_order_signed").await.unwrap();
+        store.drop_table("key_order_unsigned").await.unwrap();
+
+        let signed = store
+            .create_table::<i32, ()>("key_order_signed", false)
+            .await
+            .expect("Failed to create table");
+        for key in [-1, 2, 10, -20, 0] {
+            signed.insert(key, ()).await.unwrap();
+        }
+        let expected = match string_keys {
+            true => vec![-1, -20, 0, 10, 2],
+            false => vec![0, 2, 10, -20, -1],
+        };
+        assert_eq!(signed.keys().await.unwrap(), expected);
+        assert_eq!(signed.first().await.unwrap(), Some((expected[0], ())));
+        assert_eq!(signed.last().await.unwrap(), Some((expected[4], ())));
+        assert_eq!(signed.pop_first().await.unwrap(), Some((expected[0], ())));
+
+        let unsigned = store
+            .create_table::<u32, ()>("key_order_unsigned", false)
+            .await
+            .expect("Failed to create table");
+        for key in [9, 100, 10] {
+            unsigned.insert(key, ()).await.unwrap();
+        }
+        let (expected, in_range) = match string_keys {
+            true => (vec![10, 100, 9], vec![]),
+            false => (vec![9, 10, 100], vec![9, 10, 100]),
+        };
+        assert_eq!(unsigned.keys().await.unwrap(), expected);
+        assert_eq!(unsigned.keys_range(9..=100).await.unwrap(), in_range);
+        assert_eq!(unsigned.pop_last().await.unwrap(), Some((expected[2], ())));
+    }
+}
+
+mod raw_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::{decode_raw, encode_raw, static_table};
+
+    static_table!(get_table, "raw_test", u32, String, get_test_store);
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_raw() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_table().await;
+        table.clear().await.expect("Failed to clear");
+        table.insert(1, "one").await.expect("Failed to insert");
+        let raw = table.get_raw(1).await.unwrap().expect("Value should exist");
+        assert_eq!(decode_raw::<String>(raw.clone()).unwrap(), "one");
+        table
+            .insert_raw(2, raw)
+            .await
+            .expect("Failed to insert raw");
+        assert_eq!(table.get(2).await.unwrap(), Some("one".to_string()));
+        let raw = encode_raw(&"three".to_string()).unwrap();
+        table.insert_raw(3, raw).await.unwrap();
+        let entries = table.scan_raw(2..).await.expect("Failed to scan");
+        let entries = entries
+            .into_iter()
+            .map(|(key, raw)| (key, decode_raw::<String>(raw).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![(2, "one".to_string()), (3, "three".to_string())]
+        );
+    }
+}
+
+mod dyn_table_test {
+    use super::*;
+    extern crate self as unistore;
+
+    use crate::Error;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Entry {
+        name: String,
+        count: u32,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_dyn_table() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let store = get_test_store().await;
+        let table = store
+            .create_table::<u32, Entry>("dyn_table_test", true)
+            .await
+            .expect("Failed to create table");
+        table.clear().await.expect("Failed to clear");
+        let entry = Entry {
+            name: "one".to_string(),
+            count: 1,
+        };
+        table.insert(1, &entry).await.expect("Failed to insert");
+
+        let dyn_table = store
+            .open_dyn_table("dyn_table_test")
+            .await
+            .expect("Failed to open table");
+        let entries = dyn_table.get_range(..).await.expect("Failed to scan");
+        assert_eq!(entries.len(), 1);
+        let (key, value) = &entries[0];
+        assert_eq!(store.decode_dyn_key::<u32>(key).unwrap(), 1);
+        if cfg!(target_arch = "wasm32") || store.backend.named_values() {
+            assert_eq!(value, &json!({"name": "one", "count": 1}));
+        } else {
+            assert_eq!(value, &json!(["one", 1]));
+        }
+
+        dyn_table
+            .insert(store.dyn_key(2u32), value)
+            .await
+            .expect("Failed to insert");
+        assert_eq!(table.get(2).await.unwrap(), Some(entry));
+
+        assert!(matches!(
+            store.open_dyn_table("dyn_table_missing").await,
+            Err(Error::TableNotFound(_))
+        ));
+        let tables = store.list_tables().await.unwrap();
+        assert!(!tables.contains(&"dyn_table_missing".to_string()));
+    }
+}
+
+mod manage_tables_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::{Error, UniIndex};
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_manage_tables() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let store = get_test_store().await;
+        store
+            .drop_table("manage_old")
+            .await
+            .expect("Failed to drop");
+        store
+            .drop_table("manage_new")
+            .await
+            .expect("Failed to drop");
+        store
+            .drop_table("manage_new_index_value")
+            .await
+            .expect("Failed to drop");
+        {
+            let table = store
+                .create_table::<u32, String>("manage_old", false)
+                .await
+                .expect("Failed to create table");
+            let index: UniIndex<String, _, _> = table.create_index("value").await.unwrap();
+            table.insert(1, "one").await.unwrap();
+            index.insert("one", 1).await.unwrap();
+        }
+        let tables = store.list_tables().await.expect("Failed to list tables");
+        assert!(tables.contains(&"manage_old".to_string()));
+        assert!(tables.contains(&"manage_old_index_value".to_string()));
+
+        // The new names of the table and of its index tables must be free
+        for taken in ["manage_new", "manage_new_index_value"] {
+            store
+                .create_table::<u32, String>(taken, false)
+                .await
+                .expect("Failed to create table");
+            assert!(matches!(
+                store.rename_table("manage_old", "manage_new").await,
+                Err(Error::TableExists(table)) if table == taken
+            ));
+            store.drop_table(taken).await.expect("Failed to drop");
+        }
+        store
+            .rename_table("manage_old", "manage_new")
+            .await
+            .expect("Failed to rename");
+        assert!(matches!(
+            store.rename_table("manage_old", "manage_new").await,
+            Err(Error::TableNotFound(_))
+        ));
+        let tables = store.list_tables().await.unwrap();
+        assert!(!tables.iter().any(|table| table.starts_with("manage_old")));
+        let table = store
+            .create_table::<u32, String>("manage_new", false)
+            .await
+            .unwrap();
+        assert_eq!(table.get(1).await.unwrap(), Some("one".to_string()));
+        let index: UniIndex<String, _, _> = table.create_index("value").await.unwrap();
+        assert_eq!(index.get_keys("one").await.unwrap(), vec![1]);
+
+        store
+            .drop_table("manage_new")
+            .await
+            .expect("Failed to drop");
+        let tables = store.list_tables().await.unwrap();
+        assert!(!tables.iter().any(|table| table.starts_with("manage_new")));
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_colliding_table_names() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let store = get_test_store().await;
+        for table in ["manage_post", "manage_moved", "manage_post_index_entry"] {
+            store.drop_table(table).await.expect("Failed to drop");
+        }
+        let post = store
+            .create_table::<u32, String>("manage_post", false)
+            .await
+            .expect("Failed to create table");
+        let index: UniIndex<String, _, _> = post.create_index("owner").await.unwrap();
+        post.insert(1, "one").await.unwrap();
+        index.insert("ann", 1).await.unwrap();
+        // A regular table whose name looks like an index table of `manage_post`
+        let other = store
+            .create_table::<u32, String>("manage_post_index_entry", false)
+            .await
+            .expect("Failed to create table");
+        other.insert(1, "other").await.unwrap();
+
+        assert_eq!(post.clear().await.unwrap(), 1);
+        assert!(index.get_keys("ann").await.unwrap().is_empty());
+        assert_eq!(other.get(1).await.unwrap(), Some("other".to_string()));
+
+        post.insert(2, "two").await.unwrap();
+        index.insert("bob", 2).await.unwrap();
+        store
+            .rename_table("manage_post", "manage_moved")
+            .await
+            .expect("Failed to rename");
+        let tables = store.list_tables().await.unwrap();
+        assert!(tables.contains(&"manage_moved_index_owner".to_string()));
+        assert!(!tables.contains(&"manage_moved_index_entry".to_string()));
+        assert_eq!(other.get(1).await.unwrap(), Some("other".to_string()));
+        let moved = store
+            .create_table::<u32, String>("manage_moved", false)
+            .await
+            .unwrap();
+        let index: UniIndex<String, _, _> = moved.create_index("owner").await.unwrap();
+        assert_eq!(index.get_keys("bob").await.unwrap(), vec![2]);
+        assert_eq!(moved.remove_range(..).await.unwrap(), 1);
+        assert!(index.get_keys("bob").await.unwrap().is_empty());
+
+        store
+            .drop_table("manage_moved")
+            .await
+            .expect("Failed to drop");
+        let tables = store.list_tables().await.unwrap();
+        assert!(!tables.iter().any(|table| table.starts_with("manage_moved")));
+        assert_eq!(other.get(1).await.unwrap(), Some("other".to_string()));
+        store.drop_table("manage_post_index_entry").await.unwrap();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod builder_test {
+    use super::*;
+    use crate::UniStore;
+
+    #[tokio::test]
+    async fn test_builder() {
+        initialize();
+        let dir = std::env::temp_dir().join(format!("unistore_builder_{}", std::process::id()));
+        {
+            let store = UniStore::builder()
+                .path(&dir)
+                .file_name("custom.fjall")
+                .cache_size(1024 * 1024)
+                .write_buffer_size(1024 * 1024)
+                .fsync_ms(Some(100))
+                .channel_capacity(4)
+                .build()
+                .await
+                .expect("Failed to build store");
+            let table = store
+                .create_table::<u32, String>("builder_test", false)
+                .await
+                .expect("Failed to create table");
+            table.insert(1, "one").await.expect("Failed to insert");
+            assert_eq!(table.get(1).await.unwrap(), Some("one".to_string()));
+        }
+        assert!(dir.join("custom.fjall").exists());
+        assert!(UniStore::builder().build().await.is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod worker_test {
+    use super::*;
+    use crate::UniStore;
+    use crate::native::{Error, Worker};
+
+    #[tokio::test]
+    async fn test_worker_survives_panic() {
+        initialize();
+        let builder = UniStore::builder().temporary();
+        let worker = Worker::spawn(&builder, "worker_test", |_| Ok(0u32))
+            .await
+            .expect("Failed to spawn worker");
+        let result = worker
+            .run(|_| -> Result<(), Error> { panic!("job failed") })
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::Error::Native(Error::Panicked(message))) if message == "job failed"
+        ));
+        let result = worker.run(|count| {
+            *count += 1;
+            Ok(*count)
+        });
+        assert_eq!(result.await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fold_runs_on_worker() {
+        initialize();
+        let store = UniStore::temporary().await.unwrap();
+        let table = store
+            .create_table::<u32, u32>("fold_worker", false)
+            .await
+            .unwrap();
+        for key in 0..3 {
+            table.insert(key, key * 10).await.unwrap();
+        }
+        let caller = std::thread::current().id();
+        let (sum, on_worker) = table
+            .fold(.., (0, true), move |(sum, on_worker), _, value| {
+                (
+                    sum + value,
+                    on_worker && std::thread::current().id() != caller,
+                )
+            })
+            .await
+            .unwrap();
+        assert_eq!(sum, 30);
+        assert!(on_worker);
+    }
+}
+
+mod query_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::UniIndex;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Task {
+        status: String,
+        priority: u8,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_query() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_test_store()
+            .await
+            .create_table::<u32, Task>("query_test", false)
+            .await
+            .expect("Failed to create table");
+        let index: UniIndex<String, _, _> = table
+            .create_index("status")
+            .await
+            .expect("Failed to create index");
+        for (key, status, priority) in [
+            (1, "open", 3),
+            (2, "done", 1),
+            (3, "open", 1),
+            (4, "open", 5),
+            (5, "done", 4),
+        ] {
+            let task = Task {
+                status: status.to_string(),
+                priority,
+            };
+            table.insert(key, &task).await.unwrap();
+            index.insert(status, key).await.unwrap();
+        }
+
+        let results = table
+            .query()
+            .range(2..=4)
+            .filter(|task| task.priority > 1)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![4]);
+
+        let results = table
+            .query()
+            .index(&index, "open")
+            .sort_by_key(|task| std::cmp::Reverse(task.priority))
+            .offset(1)
+            .limit(1)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![1]);
+
+        let results = table
+            .query()
+            .index(&index, "done")
+            .range(..5)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![2]);
+    }
+}
+
+mod typed_query_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::UniStoreItem;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    enum Status {
+        Open,
+        Done,
+    }
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    #[unistore(query)]
+    struct TypedTask {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(index)]
+        owner: String,
+        status: Status,
+        priority: u8,
+    }
+
+    // Without #[unistore(query)] fields may share names with the query methods
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_test_store)]
+    struct Page {
+        #[unistore(key)]
+        id: u32,
+        limit: u32,
+        offset: u32,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_typed_query() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        for (id, owner, status, priority) in [
+            (1, "ann", Status::Done, 5),
+            (2, "ann", Status::Open, 4),
+            (3, "bob", Status::Done, 4),
+            (4, "ann", Status::Done, 2),
+        ] {
+            let task = TypedTask {
+                id,
+                owner: owner.to_string(),
+                status,
+                priority,
+            };
+            task.save().await.expect("Failed to insert value");
+        }
+
+        let results = TypedTask::query()
+            .status()
+            .eq(Status::Done)
+            .priority()
+            .gt(3)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            results.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+
+        let results = TypedTask::query()
+            .owner()
+            .eq("ann".to_string())
+            .priority()
+            .le(4)
+            .limit(1)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![2]);
+
+        let page = Page {
+            id: 1,
+            limit: 10,
+            offset: 20,
+        };
+        page.save().await.expect("Failed to insert value");
+        assert_eq!(Page::get(1).await.unwrap(), Some(page));
+    }
+}
+
+mod aggregate_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::UniIndex;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Sale {
+        category: String,
+        amount: u32,
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_aggregate() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let table = get_test_store()
+            .await
+            .create_table::<u32, Sale>("aggregate_test", false)
+            .await
+            .expect("Failed to create table");
+        let index: UniIndex<String, _, _> = table
+            .create_index("category")
+            .await
+            .expect("Failed to create index");
+        for (key, category, amount) in [(3, "food", 10), (1, "books", 25), (7, "food", 5)] {
+            let sale = Sale {
+                category: category.to_string(),
+                amount,
+            };
+            table.insert(key, &sale).await.unwrap();
+            index.insert(category, key).await.unwrap();
+        }
+
+        assert_eq!(table.min_key().await.unwrap(), Some(1));
+        assert_eq!(table.max_key().await.unwrap(), Some(7));
+        assert_eq!(table.count(|sale| sale.amount >= 10).await.unwrap(), 2);
+        let total = table
+            .fold(2.., 0, |sum, _, sale| sum + sale.amount)
+            .await
+            .unwrap();
+        assert_eq!(total, 15);
+        let sums = table
+            .group_by(
+                |sale| sale.category.clone(),
+                0,
+                |sum, _, sale| sum + sale.amount,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            sums,
+            BTreeMap::from([("books".to_string(), 25), ("food".to_string(), 15)])
+        );
+
+        let food = index
+            .fold("food", 0, |sum, _, sale| sum + sale.amount)
+            .await
+            .unwrap();
+        assert_eq!(food, 15);
+        let counts = index.group_by(0, |count, _| count + 1).await.unwrap();
+        assert_eq!(
+            counts,
+            BTreeMap::from([("books".to_string(), 1), ("food".to_string(), 2)])
+        );
+        assert_eq!(index.min_key().await.unwrap(), Some("books".to_string()));
+        assert_eq!(index.max_key().await.unwrap(), Some("food".to_string()));
+    }
+}
+
+mod memory_test {
+    use super::*;
+    extern crate self as unistore;
+    use crate::{Error, UniIndex, UniStore, static_store};
+
+    static_store!(get_memory_store, memory);
+
+    /// Runs the same operations on `store` and returns everything that was read back.
+    pub(super) async fn run(store: &UniStore) -> Vec<String> {
+        store.drop_table("memory_test").await.unwrap();
+        let table = store
+            .create_table::<u32, String>("memory_test", false)
+            .await
+            .expect("Failed to create table");
+        let index: UniIndex<String, _, _> = table
+            .create_index("parity")
+            .await
+            .expect("Failed to create index");
+        for key in [5, 30, 12, 0, 7, 10, 3] {
+            let parity = if key % 2 == 0 { "even" } else { "odd" };
+            table.insert(key, format!("v{key}")).await.unwrap();
+            index.insert(parity, key).await.unwrap();
+        }
+        vec![
+            format!("{:?}", table.get_range(..).await.unwrap()),
+            format!("{:?}", table.keys_range(3..=10).await.unwrap()),
+            format!("{:?}", table.get(7).await.unwrap()),
+            format!("{:?}", table.get_many([3, 4]).await.unwrap()),
+            format!("{:?}", table.first().await.unwrap()),
+            format!("{:?}", table.last().await.unwrap()),
+            format!("{:?}", table.pop_first().await.unwrap()),
+            format!("{:?}", table.pop_last().await.unwrap()),
+            format!("{:?}", table.remove_range(..3).await.unwrap()),
+            format!("{:?}", index.get_keys("odd").await.unwrap()),
+            format!("{:?}", index.get_keys("even").await.unwrap()),
+            format!("{:?}", table.len().await.unwrap()),
+        ]
+    }
+
+    /// Checks that a dropped table is not created again by later operations on its handle.
+    pub(super) async fn check_dropped_table(store: &UniStore) {
+        let table = store
+            .create_table::<u32, String>("dropped_test", false)
+            .await
+            .expect("Failed to create table");
+        table.insert(1, "one").await.unwrap();
+        store.drop_table("dropped_test").await.unwrap();
+
+        assert_eq!(table.get(1).await.unwrap(), None);
+        assert_eq!(table.get_many([1, 2]).await.unwrap(), vec![None, None]);
+        assert_eq!(table.len().await.unwrap(), 0);
+        assert!(table.get_range(..).await.unwrap().is_empty());
+        assert!(table.keys().await.unwrap().is_empty());
+        table.remove(1).await.unwrap();
+        assert_eq!(table.pop_first().await.unwrap(), None);
+        assert!(matches!(
+            table.insert(2, "two").await,
+            Err(Error::TableNotFound(_))
+        ));
+        let mut transaction = store.transaction();
+        transaction.insert(&table, 3, "three").unwrap();
+        assert!(matches!(
+            transaction.commit().await,
+            Err(Error::TableNotFound(_))
+        ));
+        let tables = store.list_tables().await.unwrap();
+        assert!(!tables.contains(&"dropped_test".to_string()));
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_memory_matches_persistent() {
+        let _lock = TEST_MUTEX.lock().await; // Ensure tests run sequentially
+        initialize();
+        let store = get_test_store().await;
+        let memory = get_memory_store().await;
+        let expected = run(store).await;
+        let actual = run(memory).await;
+        // Backends with string keys order numbers differently
+        if store.backend.string_keys() == memory.backend.string_keys() {
+            assert_eq!(actual, expected);
+        }
+        assert_eq!(actual[1], "[3, 5, 7, 10]");
+        check_dropped_table(store).await;
+        check_dropped_table(memory).await;
+        assert!(
+            memory
+                .list_tables()
+                .await
+                .unwrap()
+                .contains(&"memory_test".to_string())
+        );
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod temporary_store_test {
+    extern crate self as unistore;
+    use super::*;
+    use crate::{UniStore, UniStoreItem, temporary_store};
+    use serde::{Deserialize, Serialize};
+
+    temporary_store!(get_test_store);
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug)]
+    #[unistore(temporary_store = get_test_store)]
+    struct Note {
+        #[unistore(key)]
+        key: u32,
+        #[unistore(index)]
+        author: String,
+    }
+
+    /// Runs in parallel with [`test_isolated_b`], each test only sees its own notes.
+    #[tokio::test]
+    async fn test_isolated_a() {
+        initialize();
+        for key in 0..3 {
+            let author = "a".to_string();
+            Note { key, author }.save().await.unwrap();
+        }
+        assert_eq!(Note::table().await.len().await.unwrap(), 3);
+        assert_eq!(Note::get_by_author("a".to_string()).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_isolated_b() {
+        initialize();
+        let author = "b".to_string();
+        Note { key: 1, author }.save().await.unwrap();
+        assert_eq!(Note::table().await.len().await.unwrap(), 1);
+        assert!(
+            Note::get_by_author("a".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_temporary_close() {
+        initialize();
+        let store = UniStore::temporary().await.expect("Failed to create store");
+        let table = store
+            .create_table::<u32, String>("temporary_test", false)
+            .await
+            .unwrap();
+        table.insert(1, "one").await.unwrap();
+        assert_eq!(table.get(1).await.unwrap(), Some("one".to_string()));
+        store.close();
+        assert!(table.get(1).await.is_err());
+    }
+}
+
+mod backend_test {
+    use super::*;
+    use crate::{
+        Backend, BackendFuture, Entry, Error, KeyBounds, MemoryBackend, RawValue, UniStore, WriteOp,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static BATCHES: AtomicUsize = AtomicUsize::new(0);
+
+    /// Custom backend counting the atomic batches written to the wrapped memory backend.
+    #[derive(Default)]
+    struct CountingBackend {
+        inner: MemoryBackend,
+    }
+
+    impl Backend for CountingBackend {
+        fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool> {
+            self.inner.open_table(table)
+        }
+
+        fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()> {
+            self.inner.delete_table(table)
+        }
+
+        fn list_tables(&self) -> BackendFuture<'_, Vec<String>> {
+            self.inner.list_tables()
+        }
+
+        fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>> {
+            self.inner.get(table, key)
+        }
+
+        fn insert<'a>(
+            &'a self,
+            table: &'a str,
+            key: Vec<u8>,
+            value: RawValue,
+        ) -> BackendFuture<'a, ()> {
+            self.inner.insert(table, key, value)
+        }
+
+        fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()> {
+            self.inner.remove(table, key)
+        }
+
+        fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize> {
+            self.inner.len(table)
+        }
+
+        fn scan<'a>(
+            &'a self,
+            table: &'a str,
+            range: KeyBounds,
+            reverse: bool,
+            limit: Option<usize>,
+        ) -> BackendFuture<'a, Vec<Entry>> {
+            self.inner.scan(table, range, reverse, limit)
+        }
+
+        fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>> {
+            self.inner.pop(table, last)
+        }
+
+        fn write(&self, batch: Vec<WriteOp>) -> BackendFuture<'_, ()> {
+            BATCHES.fetch_add(1, Ordering::SeqCst);
+            self.inner.write(batch)
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), tokio::test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn test_custom_backend_transaction() {
+        initialize();
+        let store = UniStore::with_backend("counting", CountingBackend::default());
+        let users = store
+            .create_table::<u32, String>("users", false)
+            .await
+            .unwrap();
+        let counts = store
+            .create_table::<String, u32>("counts", false)
+            .await
+            .unwrap();
+        users.insert(1, "old").await.unwrap();
+
+        let mut tx = store.transaction();
+        tx.insert(&users, 2, "two").unwrap();
+        tx.insert(&counts, "users", 1).unwrap();
+        tx.remove(&users, 1).unwrap();
+        assert_eq!(tx.len(), 3);
+        let other = UniStore::in_memory();
+        let foreign = other
+            .create_table::<u32, String>("users", false)
+            .await
+            .unwrap();
+        assert!(matches!(
+            tx.insert(&foreign, 3, "three"),
+            Err(Error::ForeignTable(_))
+        ));
+        assert!(matches!(
+            tx.remove(&foreign, 3),
+            Err(Error::ForeignTable(_))
+        ));
+        assert_eq!(tx.len(), 3);
+        tx.commit().await.unwrap();
+
+        assert_eq!(BATCHES.load(Ordering::SeqCst), 1);
+        assert_eq!(users.keys().await.unwrap(), vec![2]);
+        assert_eq!(counts.get("users").await.unwrap(), Some(1));
+        let mut tables = store.list_tables().await.unwrap();
+        tables.sort();
+        assert_eq!(tables, vec!["counts".to_string(), "users".to_string()]);
+    }
+}
+
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod sqlite_test {
+    use super::*;
+    use crate::UniStore;
+
+    #[tokio::test]
+    async fn test_sqlite_matches_memory() {
+        initialize();
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = UniStore::builder()
+                .path(dir.path())
+                .sqlite()
+                .build()
+                .await
+                .expect("Failed to build store");
+            let expected = memory_test::run(&UniStore::in_memory()).await;
+            assert_eq!(memory_test::run(&store).await, expected);
+            memory_test::check_dropped_table(&store).await;
+        }
+
+        // The file is a plain SQLite database
+        let connection = rusqlite::Connection::open(dir.path().join("unistore.sqlite")).unwrap();
+        let count: i64 = connection
+            .query_row("SELECT COUNT(*) FROM memory_test", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 5);
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+mod blocking_test {
+    use super::*;
+    use crate::BlockingUniStore;
+
+    // A plain test without an async runtime
+    #[test]
+    fn test_blocking_store() {
+        initialize();
+        let store = BlockingUniStore::temporary().expect("Failed to open store");
+        let table = store
+            .create_table::<u32, String>("blocking_test", false)
+            .expect("Failed to create table");
+        for key in [3, 1, 2] {
+            table.insert(key, format!("v{key}")).unwrap();
+        }
+        assert_eq!(table.get(2).unwrap(), Some("v2".to_string()));
+        assert_eq!(table.keys_range(2..).unwrap(), vec![2, 3]);
+        assert_eq!(table.pop_first().unwrap(), Some((1, "v1".to_string())));
+        assert_eq!(table.len().unwrap(), 2);
+        assert!(
+            store
+                .list_tables()
+                .unwrap()
+                .contains(&"blocking_test".to_string())
+        );
+
+        store.drop_table("blocking_test").unwrap();
+        assert!(store.list_tables().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_blocking_indexes_and_queries() {
+        initialize();
+        let store = BlockingUniStore::temporary().expect("Failed to open store");
+        let table = store
+            .create_table::<u32, String>("blocking_index_test", false)
+            .expect("Failed to create table");
+        let index = table
+            .create_index::<String>("parity")
+            .expect("Failed to create index");
+        let fulltext = table
+            .create_fulltext_index("text")
+            .expect("Failed to create index");
+        for key in 1..=4 {
+            let parity = if key % 2 == 0 { "even" } else { "odd" };
+            table.insert(key, format!("{parity} {key}")).unwrap();
+            index.insert(parity, key).unwrap();
+            fulltext.insert(&format!("{parity} {key}"), key).unwrap();
+        }
+
+        assert_eq!(index.get_keys("even").unwrap(), vec![2, 4]);
+        assert_eq!(index.count("odd").unwrap(), 2);
+        assert_eq!(
+            fulltext.search("odd 3").unwrap(),
+            vec![(3, "odd 3".to_string())]
+        );
+        let query = table
+            .query()
+            .filter(|value| value.starts_with("even"))
+            .limit(1);
+        assert_eq!(
+            query.execute_blocking().unwrap(),
+            vec![(2, "even 2".to_string())]
+        );
+        assert_eq!(table.count(|value| value.starts_with("odd")).unwrap(), 2);
+        assert_eq!(table.fold(.., 0, |sum, key, _| sum + key).unwrap(), 10);
+        assert_eq!(table.max_key().unwrap(), Some(4));
+
+        let mut transaction = store.transaction();
+        transaction.insert(table.table(), 5, "odd 5").unwrap();
+        transaction.remove(table.table(), 1).unwrap();
+        transaction.commit_blocking().unwrap();
+        assert_eq!(table.keys().unwrap(), vec![2, 3, 4, 5]);
+        assert_eq!(index.remove_all("even").unwrap(), 2);
+        assert_eq!(table.keys().unwrap(), vec![3, 5]);
+    }
+}
+
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+mod blocking_item_test {
+    extern crate self as unistore;
+    use super::*;
+    use crate::{BlockingUniStoreItem, UniStore, UniStoreItem, static_store};
+    use serde::{Deserialize, Serialize};
+
+    static_store!(get_blocking_store, UniStore::builder().temporary());
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug, Clone)]
+    #[unistore(store = get_blocking_store)]
+    struct Note {
+        #[unistore(key)]
+        id: u32,
+        #[unistore(index)]
+        owner: String,
+    }
+
+    #[test]
+    fn test_blocking_items() {
+        initialize();
+        let note = Note {
+            id: 1,
+            owner: "ann".to_string(),
+        };
+        note.save_blocking().expect("Failed to save");
+        assert_eq!(Note::get_blocking(1).unwrap(), Some(note.clone()));
+        assert_eq!(
+            Note::get_by_index_blocking::<String>("owner", "ann").unwrap(),
+            vec![(1, note)]
+        );
+        assert_eq!(Note::count_by_index_blocking("owner", "ann").unwrap(), 1);
+        Note::remove_blocking(1).unwrap();
+        assert!(!Note::contains_blocking(1).unwrap());
+        assert_eq!(Note::count_by_index_blocking("owner", "ann").unwrap(), 0);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod owned_table_test {
+    use super::*;
+    use crate::{OwnedUniTable, UniIndex, UniStore};
+    use std::sync::Arc;
+
+    struct Counter {
+        table: OwnedUniTable<String, u32>,
+    }
+
+    #[tokio::test]
+    async fn test_owned_table_in_spawned_tasks() {
+        initialize();
+        let store = Arc::new(UniStore::temporary().await.unwrap());
+        let counter = Counter {
+            table: store
+                .create_owned_table("owned_test", false)
+                .await
+                .expect("Failed to create table"),
+        };
+        let tasks = (0..4u32).map(|i| {
+            let table = counter.table.clone();
+            tokio::spawn(async move { table.insert(format!("k{i}"), i).await })
+        });
+        for task in tasks.collect::<Vec<_>>() {
+            task.await.unwrap().unwrap();
+        }
+        // The tables keep the store alive
+        drop(store);
+        assert_eq!(counter.table.len().await.unwrap(), 4);
+        assert_eq!(counter.table.get("k2").await.unwrap(), Some(2));
+
+        // Indexes of owned tables are 'static as well
+        let index: UniIndex<String, _, _, _> = counter.table.create_index("parity").await.unwrap();
+        let task = tokio::spawn(async move {
+            index.insert("even", "k2").await?;
+            index.get("even").await
+        });
+        assert_eq!(task.await.unwrap().unwrap(), vec![("k2".to_string(), 2)]);
+        let query = counter.table.query().filter(|value| *value > 1);
+        assert_eq!(query.execute().await.unwrap().len(), 2);
+    }
+}
+
+#[cfg(all(feature = "json-file", not(target_arch = "wasm32")))]
+mod json_file_test {
+    use super::*;
+    use crate::UniStore;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Fixture {
+        name: String,
+        count: u32,
+    }
+
+    async fn open(dir: &std::path::Path) -> UniStore {
+        UniStore::builder()
+            .path(dir)
+            .json_file()
+            .build()
+            .await
+            .expect("Failed to build store")
+    }
+
+    #[tokio::test]
+    async fn test_json_file_roundtrip() {
+        initialize();
+        let dir = tempfile::tempdir().unwrap();
+        let one = Fixture {
+            name: "one".to_string(),
+            count: 1,
+        };
+        let two = Fixture {
+            name: "two".to_string(),
+            count: 2,
+        };
+        {
+            let store = open(dir.path()).await;
+            let table = store
+                .create_table::<String, Fixture>("fixtures", false)
+                .await
+                .unwrap();
+            table.insert("b".to_string(), two.clone()).await.unwrap();
+            table.insert("a".to_string(), one.clone()).await.unwrap();
+            table.insert("c".to_string(), two.clone()).await.unwrap();
+            table.remove("c".to_string()).await.unwrap();
+        }
+
+        // Each table is a pretty-printed JSON object keyed by the key strings
+        let files = dir.path().join("unistore.json");
+        let text = std::fs::read_to_string(files.join("fixtures.json")).unwrap();
+        assert!(text.contains("\n  \"a\": {"));
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            json,
+            json!({"a": {"name": "one", "count": 1}, "b": {"name": "two", "count": 2}})
+        );
+        assert!(!files.join("fixtures.json.tmp").exists());
+
+        // Edits to the files are picked up when the store is opened again
+        let edited = json!({"a": {"name": "edited", "count": 3}, "b": json["b"]});
+        std::fs::write(files.join("fixtures.json"), edited.to_string()).unwrap();
+        let store = open(dir.path()).await;
+        let table = store
+            .create_table::<String, Fixture>("fixtures", false)
+            .await
+            .unwrap();
+        let edited = Fixture {
+            name: "edited".to_string(),
+            count: 3,
+        };
+        assert_eq!(
+            table.get_range(..).await.unwrap(),
+            vec![("a".to_string(), edited), ("b".to_string(), two)]
+        );
+        store.drop_table("fixtures").await.unwrap();
+        assert!(!files.join("fixtures.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_json_file_failed_save() {
+        initialize();
+        let dir = tempfile::tempdir().unwrap();
+        let store = open(dir.path()).await;
+        let table = store
+            .create_table::<String, u32>("counts", false)
+            .await
+            .unwrap();
+        table.insert("a".to_string(), 1).await.unwrap();
+
+        // A directory in place of the temporary file makes saving fail
+        let files = dir.path().join("unistore.json");
+        std::fs::create_dir(files.join("counts.json.tmp")).unwrap();
+        assert!(table.insert("a".to_string(), 2).await.is_err());
+        assert!(table.remove("a".to_string()).await.is_err());
+        assert_eq!(table.get("a".to_string()).await.unwrap(), Some(1));
+
+        // Removing from a missing table doesn't create it
+        let missing = store
+            .create_table::<String, u32>("missing", false)
+            .await
+            .unwrap();
+        store.drop_table("missing").await.unwrap();
+        missing.remove("a".to_string()).await.unwrap();
+        assert_eq!(missing.pop_first().await.unwrap(), None);
+        assert!(!files.join("missing.json").exists());
+        assert!(
+            !store
+                .list_tables()
+                .await
+                .unwrap()
+                .contains(&"missing".to_string())
+        );
+        memory_test::check_dropped_table(&store).await;
+
+        // A failed save of one table of a batch leaves the tables saved before it unchanged
+        let other = store
+            .create_table::<String, u32>("another", false)
+            .await
+            .unwrap();
+        let mut transaction = store.transaction();
+        transaction.insert(&other, "b", 1).unwrap();
+        transaction.insert(&table, "b", 2).unwrap();
+        assert!(transaction.commit().await.is_err());
+        assert_eq!(other.get("b".to_string()).await.unwrap(), None);
+        assert!(!files.join("another.json.tmp").exists());
+        let text = std::fs::read_to_string(files.join("another.json")).unwrap();
+        assert_eq!(text.trim(), "{}");
+    }
+
+    #[tokio::test]
+    async fn test_json_file_table_names() {
+        initialize();
+        let dir = tempfile::tempdir().unwrap();
+        let names = ["../escape", "a/b", "Users", "users", "ünïcode"];
+        {
+            let store = open(dir.path()).await;
+            for (count, name) in names.iter().enumerate() {
+                let table = store
+                    .create_table::<String, u32>(name, false)
+                    .await
+                    .unwrap();
+                table
+                    .insert("count".to_string(), count as u32)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // All files are inside the store directory, one per table
+        let files = dir.path().join("unistore.json");
+        assert!(!dir.path().join("escape.json").exists());
+        assert_eq!(std::fs::read_dir(&files).unwrap().count(), names.len());
+        assert!(files.join("%2E%2E%2Fescape.json").exists());
+
+        let store = open(dir.path()).await;
+        let mut tables = store.list_tables().await.unwrap();
+        tables.sort();
+        let mut expected = names.map(str::to_string).to_vec();
+        expected.sort();
+        assert_eq!(tables, expected);
+        for (count, name) in names.iter().enumerate() {
+            let table = store
+                .create_table::<String, u32>(name, false)
+                .await
+                .unwrap();
+            let value = table.get("count".to_string()).await.unwrap();
+            assert_eq!(value, Some(count as u32));
+        }
+    }
+}
+
+#[cfg(all(feature = "redb", not(target_arch = "wasm32")))]
+mod redb_test {
+    extern crate self as unistore;
+    use super::*;
+    use crate::{UniStore, UniStoreItem, static_store};
+    use serde::{Deserialize, Serialize};
+
+    static_store!(get_test_store, UniStore::builder().temporary().redb());
+
+    #[derive(UniStoreItem, Serialize, Deserialize, PartialEq, Debug)]
+    #[unistore(store = get_test_store)]
+    struct Task {
+        #[unistore(key)]
+        key: u32,
+        #[unistore(index)]
+        owner: String,
+    }
+
+    #[tokio::test]
+    async fn test_redb_matches_memory() {
+        initialize();
+        let expected = memory_test::run(&UniStore::in_memory()).await;
+        assert_eq!(memory_test::run(get_test_store().await).await, expected);
+        memory_test::check_dropped_table(get_test_store().await).await;
+
+        for (key, owner) in [(1, "ann"), (2, "bob"), (3, "ann")] {
+            let owner = owner.to_string();
+            Task { key, owner }.save().await.unwrap();
+        }
+        assert_eq!(
+            Task::get_by_owner("ann".to_string()).await.unwrap().len(),
+            2
+        );
+        Task::remove(1).await.unwrap();
+        assert_eq!(
+            Task::get_by_owner("ann".to_string()).await.unwrap().len(),
+            1
+        );
+    }
+}
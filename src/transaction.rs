@@ -0,0 +1,80 @@
+use crate::backend::WriteOp;
+use crate::{AsKey, AsValue, Error, Key, StoreRef, UniStore, UniTable, Value, raw};
+
+/// Writes to one or more tables of a store that are applied atomically,
+/// created by [`UniStore::transaction`].
+///
+/// Only the given entries are written: index entries are not updated and relations
+/// are not checked. Nothing is written if the transaction is dropped without [`Self::commit`].
+pub struct Transaction<'a> {
+    store: &'a UniStore,
+    batch: Vec<WriteOp>,
+}
+
+impl UniStore {
+    /// Starts a transaction that writes to tables of this store atomically.
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction {
+            store: self,
+            batch: Vec::new(),
+        }
+    }
+}
+
+impl Transaction<'_> {
+    /// Inserts `value` into `table` when the transaction is committed.
+    /// Fails with [`Error::ForeignTable`] if the table belongs to a different store.
+    pub fn insert<K: Key, V: Value, S: StoreRef>(
+        &mut self,
+        table: &UniTable<'_, K, V, S>,
+        key: impl AsKey<K>,
+        value: impl AsValue<V>,
+    ) -> Result<&mut Self, Error> {
+        self.check_store(table)?;
+        self.batch.push(WriteOp::Insert {
+            table: table.name.clone(),
+            key: table.encode_key(key.as_key()),
+            value: raw::encode(&value, self.store.backend.named_values())?,
+        });
+        Ok(self)
+    }
+
+    /// Removes `key` from `table` when the transaction is committed.
+    /// Fails with [`Error::ForeignTable`] if the table belongs to a different store.
+    pub fn remove<K: Key, V: Value, S: StoreRef>(
+        &mut self,
+        table: &UniTable<'_, K, V, S>,
+        key: impl AsKey<K>,
+    ) -> Result<&mut Self, Error> {
+        self.check_store(table)?;
+        self.batch.push(WriteOp::Remove {
+            table: table.name.clone(),
+            key: table.encode_key(key.as_key()),
+        });
+        Ok(self)
+    }
+
+    /// Number of writes in the transaction.
+    pub fn len(&self) -> usize {
+        self.batch.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batch.is_empty()
+    }
+
+    /// Applies all writes atomically.
+    pub async fn commit(self) -> Result<(), Error> {
+        self.store.backend.write(self.batch).await
+    }
+
+    fn check_store<K: Key, V: Value, S: StoreRef>(
+        &self,
+        table: &UniTable<'_, K, V, S>,
+    ) -> Result<(), Error> {
+        if !std::ptr::eq(self.store, &*table.store) {
+            return Err(Error::ForeignTable(table.name.clone()));
+        }
+        Ok(())
+    }
+}
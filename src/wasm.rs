@@ -1,11 +1,14 @@
+use std::ops::Bound;
 use std::rc::Rc;
 
-use idb::{DatabaseEvent, Factory, ObjectStoreParams};
+use idb::{DatabaseEvent, Event, Factory, ObjectStoreParams, Request};
+use serde::Serialize;
 use serde_wasm_bindgen::Serializer;
 use std::sync::Mutex;
 use wasm_bindgen::JsValue;
 
-use crate::{AsKey, AsValue, Key, UniStore, UniTable, Value};
+use crate::backend::{Backend, BackendFuture, Entry, KeyBounds, WriteOp, is_empty_range};
+use crate::{RawValue, Value};
 
 thread_local! {
     static DBS: Mutex<Vec<Rc<idb::Database>>> = Mutex::new(Vec::new());
@@ -56,17 +59,86 @@ impl std::fmt::Display for Error {
     }
 }
 
-pub async fn create_database(name: &str) -> Result<Database, Error> {
-    // Get a factory instance from global scope
-    let factory = Factory::new()?;
+/// The IndexedDB backend. Every table is an object store with string keys.
+pub struct IdbBackend {
+    db: Database,
+    name: String,
+}
+
+impl IdbBackend {
+    pub(crate) async fn open(name: &str) -> Result<Self, Error> {
+        // Get a factory instance from global scope
+        let factory = Factory::new()?;
+
+        // Create an open request for the database
+        let open_request = factory.open(name, None).unwrap();
 
-    // Create an open request for the database
-    let open_request = factory.open(name, None).unwrap();
+        // `await` open request
+        let mut db = open_request.await?;
+        db.on_version_change(|event| event.database().expect("database").close());
+        Ok(IdbBackend {
+            db: Database::new(db),
+            name: name.to_string(),
+        })
+    }
 
-    // `await` open request
-    let mut db = open_request.await?;
-    db.on_version_change(|event| event.database().expect("database").close());
-    Ok(Database::new(db))
+    /// Reopens the database with a new version to change its object stores in `f`,
+    /// which also gets the version change transaction.
+    async fn upgrade(
+        &self,
+        f: impl FnOnce(&idb::Database, idb::Transaction) + 'static,
+    ) -> Result<(), Error> {
+        let version = self
+            .db
+            .get_db()
+            .version()
+            .expect("Failed to get database version");
+        let mut open_request = Factory::new()?.open(&self.name, Some(version + 1)).unwrap();
+        open_request.on_upgrade_needed(move |event| {
+            let edb = event.database().unwrap();
+            let tx = event.target().unwrap().transaction().unwrap();
+            f(&edb, tx);
+        });
+        let mut new_db = open_request.await?;
+        new_db.on_version_change(|event| event.database().expect("database").close());
+        self.db.update(new_db);
+        Ok(())
+    }
+
+    fn exists(&self, table: &str) -> bool {
+        self.db
+            .get_db()
+            .store_names()
+            .iter()
+            .any(|name| name == table)
+    }
+
+    /// Removes and returns the first entry when iterating the store in `direction`,
+    /// in a single readwrite transaction.
+    async fn pop_entry(
+        &self,
+        table: &str,
+        direction: idb::CursorDirection,
+    ) -> Result<Option<Entry>, Error> {
+        if !self.exists(table) {
+            return Ok(None);
+        }
+        with_transaction(
+            &self.db,
+            &[table],
+            idb::TransactionMode::ReadWrite,
+            |tx| async move {
+                let store = tx.object_store(table)?;
+                let Some(cursor) = store.open_cursor(None, Some(direction))?.await? else {
+                    return Ok(None);
+                };
+                let entry = (from_js_key(cursor.key()?), cursor.value()?);
+                cursor.delete()?.await?;
+                Ok(Some(entry))
+            },
+        )
+        .await
+    }
 }
 
 async fn with_transaction<FUNC, FUT, R>(
@@ -86,244 +158,407 @@ where
     result
 }
 
-pub async fn create_table<'a, K: Key, V: Value>(
-    store: &'a UniStore,
-    name: &str,
-    replace_if_incomatible: bool,
-) -> Result<UniTable<'a, K, V>, Error> {
-    let db = store.db.get_db();
-    let mut replace = false;
-
-    'exists_check: {
-        if db.store_names().iter().any(|s| s == name) {
-            // If the store already exists, check if the types match
-            let cursor = with_transaction(
-                &store.db,
-                &[name],
+impl Backend for IdbBackend {
+    fn open_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            if self.exists(table) {
+                return Ok(false);
+            }
+            let name = table.to_string();
+            self.upgrade(move |edb, _| {
+                let _ = edb
+                    .create_object_store(&name, ObjectStoreParams::new())
+                    .unwrap();
+            })
+            .await?;
+            Ok(true)
+        })
+    }
+
+    fn delete_table<'a>(&'a self, table: &'a str) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            if !self.exists(table) {
+                return Ok(());
+            }
+            let name = table.to_string();
+            self.upgrade(move |edb, _| edb.delete_object_store(&name).unwrap())
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn list_tables(&self) -> BackendFuture<'_, Vec<String>> {
+        Box::pin(async move { Ok(self.db.get_db().store_names()) })
+    }
+
+    fn get<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, Option<RawValue>> {
+        Box::pin(async move {
+            if !self.exists(table) {
+                return Ok(None);
+            }
+            let key = to_js_key(key)?;
+            let value = with_transaction(
+                &self.db,
+                &[table],
                 idb::TransactionMode::ReadOnly,
                 |tx| async move {
-                    let obj_store = tx.object_store(name)?;
-                    let cursor = obj_store.open_cursor(None, None)?.await?;
-                    Ok(cursor)
+                    let store = tx.object_store(table)?;
+                    Ok(store.get(key)?.await?)
                 },
             )
             .await?;
-            if let Some(cursor) = cursor {
-                let key = cursor.key()?;
-                let value = cursor.value()?;
-                if let Err(e) =
-                    K::from_key_string(&key.as_string().expect("Key has to be a string"))
-                {
-                    if replace_if_incomatible {
-                        replace = true;
-                        break 'exists_check; // If we are replacing, break to create a new store
+            Ok(value)
+        })
+    }
+
+    fn get_many<'a>(
+        &'a self,
+        table: &'a str,
+        keys: Vec<Vec<u8>>,
+    ) -> BackendFuture<'a, Vec<Option<RawValue>>> {
+        Box::pin(async move {
+            if !self.exists(table) {
+                return Ok(vec![None; keys.len()]);
+            }
+            let keys = keys
+                .into_iter()
+                .map(to_js_key)
+                .collect::<Result<Vec<_>, _>>()?;
+            let values = with_transaction(
+                &self.db,
+                &[table],
+                idb::TransactionMode::ReadOnly,
+                |tx| async move {
+                    let store = tx.object_store(table)?;
+                    let mut values = Vec::with_capacity(keys.len());
+                    for key in keys {
+                        values.push(store.get(key)?.await?);
                     }
-                    return Err(e.into());
-                }
-                if let Err(e) = serde_wasm_bindgen::from_value::<V>(value) {
-                    if replace_if_incomatible {
-                        replace = true;
-                        break 'exists_check; // If we are replacing, break to create a new store
+                    Ok(values)
+                },
+            )
+            .await?;
+            Ok(values)
+        })
+    }
+
+    fn insert<'a>(
+        &'a self,
+        table: &'a str,
+        key: Vec<u8>,
+        value: RawValue,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            if !self.exists(table) {
+                return Err(crate::Error::TableNotFound(table.to_string()));
+            }
+            let key = to_js_key(key)?;
+            with_transaction(
+                &self.db,
+                &[table],
+                idb::TransactionMode::ReadWrite,
+                |tx| async move {
+                    let store = tx.object_store(table)?;
+                    store.put(&value, Some(&key))?.await?;
+                    Ok(())
+                },
+            )
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, table: &'a str, key: Vec<u8>) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            if !self.exists(table) {
+                return Ok(());
+            }
+            let key = to_js_key(key)?;
+            with_transaction(
+                &self.db,
+                &[table],
+                idb::TransactionMode::ReadWrite,
+                |tx| async move {
+                    let store = tx.object_store(table)?;
+                    store.delete(key)?.await?;
+                    Ok(())
+                },
+            )
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn len<'a>(&'a self, table: &'a str) -> BackendFuture<'a, usize> {
+        Box::pin(async move {
+            if !self.exists(table) {
+                return Ok(0);
+            }
+            let count = with_transaction(
+                &self.db,
+                &[table],
+                idb::TransactionMode::ReadOnly,
+                |tx| async move {
+                    let store = tx.object_store(table)?;
+                    Ok(store.count(None)?.await?)
+                },
+            )
+            .await?;
+            Ok(count as usize)
+        })
+    }
+
+    fn scan<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Entry>> {
+        Box::pin(async move {
+            // IndexedDB rejects key ranges that can't contain any key
+            if !self.exists(table) || is_empty_range(&range) {
+                return Ok(Vec::new());
+            }
+            let query = key_range(range)?.map(idb::Query::KeyRange);
+            let direction = if reverse {
+                idb::CursorDirection::Prev
+            } else {
+                idb::CursorDirection::Next
+            };
+            let limit = limit.unwrap_or(usize::MAX);
+            let entries = with_transaction(
+                &self.db,
+                &[table],
+                idb::TransactionMode::ReadOnly,
+                |tx| async move {
+                    let store = tx.object_store(table)?;
+                    let mut entries = Vec::new();
+                    let cursor = store.open_cursor(query, Some(direction))?.await?;
+                    let mut cursor = match cursor {
+                        Some(cursor) => cursor.into_managed(),
+                        None => return Ok(Vec::new()),
+                    };
+                    while entries.len() < limit {
+                        let Some(key) = cursor.key()? else {
+                            break;
+                        };
+                        let Some(value) = cursor.value()? else {
+                            break;
+                        };
+                        entries.push((from_js_key(key), value));
+                        if cursor.next(None).await.is_err() {
+                            break;
+                        }
                     }
-                    return Err(Error::ValueTypeMismatch(e));
-                }
+                    Ok(entries)
+                },
+            )
+            .await?;
+            Ok(entries)
+        })
+    }
+
+    fn scan_keys<'a>(
+        &'a self,
+        table: &'a str,
+        range: KeyBounds,
+        reverse: bool,
+        limit: Option<usize>,
+    ) -> BackendFuture<'a, Vec<Vec<u8>>> {
+        Box::pin(async move {
+            // IndexedDB rejects key ranges that can't contain any key
+            if !self.exists(table) || is_empty_range(&range) {
+                return Ok(Vec::new());
             }
-            return Ok(UniTable {
-                store,
-                name: name.to_string(),
-                phantom: std::marker::PhantomData,
-            });
-        }
+            let query = key_range(range)?.map(idb::Query::KeyRange);
+            let keys = with_transaction(
+                &self.db,
+                &[table],
+                idb::TransactionMode::ReadOnly,
+                |tx| async move {
+                    let store = tx.object_store(table)?;
+                    // A count of 0 means no limit for getAllKeys
+                    if !reverse && limit != Some(0) {
+                        let limit = limit.map(|limit| limit.min(u32::MAX as usize) as u32);
+                        return Ok(store.get_all_keys(query, limit)?.await?);
+                    }
+                    // getAllKeys has no direction, so reverse scans walk a key cursor
+                    let limit = limit.unwrap_or(usize::MAX);
+                    let mut keys = Vec::new();
+                    let cursor = store
+                        .open_key_cursor(query, Some(idb::CursorDirection::Prev))?
+                        .await?;
+                    let mut cursor = match cursor {
+                        Some(cursor) => cursor.into_managed(),
+                        None => return Ok(Vec::new()),
+                    };
+                    while keys.len() < limit {
+                        let Some(key) = cursor.key()? else {
+                            break;
+                        };
+                        keys.push(key);
+                        if cursor.next(None).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(keys)
+                },
+            )
+            .await?;
+            Ok(keys.into_iter().map(from_js_key).collect())
+        })
     }
 
-    // else create a new object store
-    let store_params = ObjectStoreParams::new();
-    let version = db.version().expect("Failed to get database version");
-
-    let mut open_request = Factory::new()?
-        .open(&store.name, Some(version + 1))
-        .unwrap();
-    let name_string = name.to_string();
-    open_request.on_upgrade_needed(move |event| {
-        let edb = event.database().unwrap();
-        if replace {
-            edb.delete_object_store(&name_string).unwrap();
-        }
-        let _ = edb.create_object_store(&name_string, store_params).unwrap();
-    });
-    let mut new_db = open_request.await?;
-    new_db.on_version_change(|event| event.database().expect("database").close());
-    store.db.update(new_db);
-
-    Ok(UniTable {
-        store,
-        name: name.to_string(),
-        phantom: std::marker::PhantomData,
-    })
-}
+    fn pop<'a>(&'a self, table: &'a str, last: bool) -> BackendFuture<'a, Option<Entry>> {
+        let direction = if last {
+            idb::CursorDirection::Prev
+        } else {
+            idb::CursorDirection::Next
+        };
+        Box::pin(async move { Ok(self.pop_entry(table, direction).await?) })
+    }
 
-pub async fn insert<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-    value: impl AsValue<V>,
-) -> Result<(), Error> {
-    with_transaction(
-        &table.store.db,
-        &[&table.name],
-        idb::TransactionMode::ReadWrite,
-        |tx| async move {
-            let store = tx.object_store(&table.name)?;
-            let value = &value.serialize(&Serializer::json_compatible()).unwrap();
-            let key = JsValue::from_str(&key.as_key().to_key_string());
-            store.put(value, Some(&key))?.await?;
+    fn write(&self, mut batch: Vec<WriteOp>) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            // Inserts into missing stores fail, removals from them are skipped
+            for op in &batch {
+                if let WriteOp::Insert { table, .. } = op
+                    && !self.exists(table)
+                {
+                    return Err(crate::Error::TableNotFound(table.clone()));
+                }
+            }
+            batch.retain(|op| match op {
+                WriteOp::Insert { .. } => true,
+                WriteOp::Remove { table, .. } => self.exists(table),
+            });
+            // Owned names, so the batch can be moved into the transaction
+            let mut tables: Vec<String> = Vec::new();
+            for op in &batch {
+                let (WriteOp::Insert { table, .. } | WriteOp::Remove { table, .. }) = op;
+                if !tables.contains(table) {
+                    tables.push(table.clone());
+                }
+            }
+            if tables.is_empty() {
+                return Ok(());
+            }
+            let tables: Vec<&str> = tables.iter().map(String::as_str).collect();
+            with_transaction(
+                &self.db,
+                &tables,
+                idb::TransactionMode::ReadWrite,
+                |tx| async move {
+                    for op in batch {
+                        match op {
+                            WriteOp::Insert { table, key, value } => {
+                                let store = tx.object_store(&table)?;
+                                store.put(&value, Some(&to_js_key(key)?))?.await?;
+                            }
+                            WriteOp::Remove { table, key } => {
+                                let store = tx.object_store(&table)?;
+                                store.delete(to_js_key(key)?)?.await?;
+                            }
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .await?;
             Ok(())
-        },
-    )
-    .await?;
-    Ok(())
-}
-
-pub async fn contains<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-) -> Result<bool, Error> {
-    let key = JsValue::from_str(&key.as_key().to_key_string());
-    let result = with_transaction(
-        &table.store.db,
-        &[&table.name],
-        idb::TransactionMode::ReadOnly,
-        |tx| async move {
-            let store = tx.object_store(&table.name)?;
-            let val = store.get(key)?.await?;
-            Ok(val)
-        },
-    )
-    .await?;
-    Ok(result.is_some())
-}
+        })
+    }
 
-pub async fn get<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-) -> Result<Option<V>, Error> {
-    let key = JsValue::from_str(&key.as_key().to_key_string());
-    let result = with_transaction(
-        &table.store.db,
-        &[&table.name],
-        idb::TransactionMode::ReadOnly,
-        |tx| async move {
-            let store = tx.object_store(&table.name)?;
-            let result = store.get(key)?.await?;
-            Ok(result)
-        },
-    )
-    .await?;
-    if let Some(value) = result {
-        let value: V = serde_wasm_bindgen::from_value(value)?;
-        Ok(Some(value))
-    } else {
-        Ok(None)
+    fn rename_tables(
+        &self,
+        renames: Vec<(String, String)>,
+        batch: Vec<WriteOp>,
+    ) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            // The tables as they are after the renames
+            let exists = |table: &str| {
+                renames.iter().any(|(_, new)| new == table)
+                    || (self.exists(table) && !renames.iter().any(|(old, _)| old == table))
+            };
+            let mut ops = Vec::with_capacity(batch.len());
+            for op in batch {
+                match op {
+                    WriteOp::Insert { table, .. } if !exists(&table) => {
+                        return Err(crate::Error::TableNotFound(table));
+                    }
+                    WriteOp::Insert { table, key, value } => {
+                        ops.push((table, to_js_key(key)?, Some(value)))
+                    }
+                    WriteOp::Remove { table, key } if exists(&table) => {
+                        ops.push((table, to_js_key(key)?, None))
+                    }
+                    WriteOp::Remove { .. } => {}
+                }
+            }
+            // Object stores are renamed in place, so the renames and the batch are applied
+            // in the version change transaction of a single upgrade
+            self.upgrade(move |_, tx| {
+                let apply = || {
+                    for (old, new) in &renames {
+                        tx.object_store(old)?.set_name(new);
+                    }
+                    for (table, key, value) in ops {
+                        let store = tx.object_store(&table)?;
+                        match value {
+                            Some(value) => drop(store.put(&value, Some(&key))?),
+                            None => drop(store.delete(key)?),
+                        }
+                    }
+                    Ok::<_, idb::Error>(())
+                };
+                if let Err(e) = apply() {
+                    tracing::error!("Failed to rename tables: {e}");
+                    let _ = tx.abort();
+                }
+            })
+            .await?;
+            Ok(())
+        })
     }
 }
 
-pub async fn len<K: Key, V: Value>(table: &UniTable<'_, K, V>) -> Result<usize, Error> {
-    let count = with_transaction(
-        &table.store.db,
-        &[&table.name],
-        idb::TransactionMode::ReadOnly,
-        |tx| async move {
-            let store = tx.object_store(&table.name)?;
-            let count = store.count(None)?.await?;
-            Ok(count)
-        },
-    )
-    .await?;
-    Ok(count as usize)
+fn to_js_key(key: Vec<u8>) -> Result<JsValue, Error> {
+    let key = String::from_utf8(key).map_err(|e| Error::CrateError(e.to_string()))?;
+    Ok(JsValue::from_str(&key))
 }
 
-pub async fn remove<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    key: impl AsKey<K>,
-) -> Result<(), Error> {
-    with_transaction(
-        &table.store.db,
-        &[&table.name],
-        idb::TransactionMode::ReadWrite,
-        |tx| async move {
-            let store = tx.object_store(&table.name)?;
-            let key = JsValue::from_str(&key.as_key().to_key_string());
-            store.delete(key)?.await?;
-            Ok(())
-        },
-    )
-    .await?;
-    Ok(())
+fn from_js_key(key: JsValue) -> Vec<u8> {
+    key.as_string()
+        .expect("Key should be a string")
+        .into_bytes()
 }
 
-pub async fn is_empty<K: Key, V: Value>(table: &UniTable<'_, K, V>) -> Result<bool, Error> {
-    len(table).await.map(|count| count == 0)
+/// Converts encoded key bounds into an IndexedDB key range, `None` meaning the whole store.
+fn key_range(range: KeyBounds) -> Result<Option<idb::KeyRange>, Error> {
+    let bound = |bound: Bound<Vec<u8>>| match bound {
+        Bound::Included(key) => Ok(Some((to_js_key(key)?, false))),
+        Bound::Excluded(key) => Ok(Some((to_js_key(key)?, true))),
+        Bound::Unbounded => Ok::<_, Error>(None),
+    };
+    let range = match (bound(range.0)?, bound(range.1)?) {
+        (Some((lower, lower_open)), Some((upper, upper_open))) => Some(idb::KeyRange::bound(
+            &lower,
+            &upper,
+            Some(lower_open),
+            Some(upper_open),
+        )?),
+        (Some((lower, open)), None) => Some(idb::KeyRange::lower_bound(&lower, Some(open))?),
+        (None, Some((upper, open))) => Some(idb::KeyRange::upper_bound(&upper, Some(open))?),
+        (None, None) => None,
+    };
+    Ok(range)
 }
 
-pub async fn get_prefix<K: Key, V: Value>(
-    table: &UniTable<'_, K, V>,
-    prefix: impl AsKey<K>,
-) -> Result<Vec<(K, V)>, Error> {
-    let key_string = prefix.as_key().to_key_string();
-    let key = JsValue::from_str(&key_string);
-    let successor = JsValue::from_str(&get_successor(&key_string));
-    tracing::info!("Key: {key:?}, Successor: {successor:?}");
-    let result = with_transaction(
-        &table.store.db,
-        &[&table.name],
-        idb::TransactionMode::ReadOnly,
-        |tx| async move {
-            let store = tx.object_store(&table.name)?;
-            let mut values = Vec::new();
-            let cursor = store
-                .open_cursor(
-                    Some(idb::Query::KeyRange(idb::KeyRange::bound(
-                        &key,
-                        &successor,
-                        None,
-                        Some(true),
-                    )?)),
-                    None,
-                )?
-                .await?;
-            let mut cursor = match cursor {
-                Some(cursor) => cursor.into_managed(),
-                None => return Ok(Vec::new()),
-            };
-            loop {
-                let Some(key) = cursor.key()? else {
-                    break;
-                };
-                let Some(value) = cursor.value()? else {
-                    break;
-                };
-                values.push((key, value));
-                if cursor.next(None).await.is_err() {
-                    break;
-                }
-            }
-            Ok(values)
-        },
-    )
-    .await?;
-    result
-        .into_iter()
-        .map(|(key, value)| {
-            let key_str = key.as_string().expect("Key should be a string");
-            let key = K::from_key_string(&key_str).map_err(Error::from)?;
-            let value: V = serde_wasm_bindgen::from_value(value).map_err(Error::from)?;
-            Ok((key, value))
-        })
-        .collect()
+pub fn encode_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, Error> {
+    Ok(value.serialize(&Serializer::json_compatible())?)
 }
 
-fn get_successor(val: &str) -> String {
-    let bytes = &val[..val.len() - 1];
-    let c = val.chars().last().unwrap();
-    let next = std::char::from_u32(c as u32 + 1).unwrap_or(c);
-    format!("{bytes}{next}")
+pub fn decode_value<V: Value>(value: JsValue) -> Result<V, Error> {
+    Ok(serde_wasm_bindgen::from_value(value)?)
 }
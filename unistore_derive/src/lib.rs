@@ -1,7 +1,7 @@
 use proc_macro_error::{abort, emit_warning, proc_macro_error};
 use proc_macro2::TokenStream;
 use quote::{ToTokens, format_ident, quote};
-use syn::{Data, DeriveInput, Ident, Meta, parse_macro_input};
+use syn::{Data, DeriveInput, Ident, Meta, Token, parse_macro_input, punctuated::Punctuated};
 
 fn snake_case(s: &str) -> String {
     s.chars()
@@ -25,19 +25,46 @@ struct Index {
     name: Ident,
     ty: TokenStream,
     path: TokenStream,
+    normalize: Option<TokenStream>,
+    /// The index is built from an `Option`, `path` evaluates to an `Option<String>`.
+    optional: bool,
+}
+/// A field referencing the key of another `UniStoreItem`.
+struct Reference {
+    field: Ident,
+    parent: TokenStream,
+    on_delete: OnDelete,
+    optional: bool,
+    /// Name of the loader generated on the parent, defaults to the plural of the child.
+    related_name: Option<Ident>,
+}
+#[derive(Clone, Copy, PartialEq)]
+enum OnDelete {
+    Cascade,
+    Restrict,
+    SetNull,
 }
 struct StructArgs {
     get_store: TokenStream,
+    /// Whether the store function returns different stores, see `temporary_store!`.
+    per_store: bool,
     key: TokenStream,
     key_path: TokenStream,
     indices: Vec<Index>,
+    fulltext: Vec<Index>,
+    references: Vec<Reference>,
+    query: bool,
 }
 impl StructArgs {
     fn from_attrs(input: &DeriveInput) -> Self {
         let mut store = TokenStream::new();
+        let mut per_store = false;
         let mut key = TokenStream::new();
         let mut key_path = TokenStream::new();
         let mut indices = Vec::new();
+        let mut fulltext = Vec::new();
+        let mut references = Vec::new();
+        let mut query = false;
         // parse attributes on the struct
         for attr in &input.attrs {
             let Meta::List(ref meta_list) = attr.meta else {
@@ -54,6 +81,39 @@ impl StructArgs {
                 Meta::NameValue(nv) if nv.path.is_ident("store") => {
                     store = nv.value.to_token_stream();
                 }
+                // Check for `#[unistore(temporary_store = ...)]` attribute
+                Meta::NameValue(nv) if nv.path.is_ident("temporary_store") => {
+                    store = nv.value.to_token_stream();
+                    per_store = true;
+                }
+                // Check for `#[unistore(index(name = "...", with = ...))]` attribute
+                Meta::List(list) if list.path.is_ident("index") => {
+                    let options = IndexOptions::parse(&list);
+                    let (Some(name), Some(with)) = (options.name, options.with) else {
+                        abort!(
+                            list,
+                            "Expected `name` and `with` for an index on the struct"
+                        );
+                    };
+                    let ty = options.ty.unwrap_or_else(|| quote! { String });
+                    let path = quote! {
+                        {
+                            use unistore::{AsKey, Key};
+                            AsKey::<#ty>::as_key(#with(self)).to_key_string()
+                        }
+                    };
+                    indices.push(Index {
+                        name,
+                        ty,
+                        path,
+                        normalize: options.normalize,
+                        optional: false,
+                    });
+                }
+                // Check for `#[unistore(query)]` attribute
+                Meta::Path(path) if path.is_ident("query") => {
+                    query = true;
+                }
                 _ => emit_warning!(attr, "Unsupported unistore attribute"),
             }
         }
@@ -62,6 +122,9 @@ impl StructArgs {
         };
         // parse attributes on the fields
         for field in &struc.fields {
+            let mut parent = None;
+            let mut on_delete = None;
+            let mut related_name = None;
             for attr in &field.attrs {
                 let Meta::List(ref meta_list) = attr.meta else {
                     continue;
@@ -69,45 +132,186 @@ impl StructArgs {
                 if !meta_list.path.is_ident("unistore") {
                     continue;
                 }
-                let inner = meta_list
-                    .parse_args::<Meta>()
+                let metas = meta_list
+                    .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
                     .expect("Failed to parse unistore attribute");
-                match inner {
-                    // Check for `#[unistore(key)]` attribute
-                    Meta::Path(p) if p.is_ident("key") => {
-                        if key.is_empty() {
-                            key = field.ty.to_token_stream();
-                            field.ident.as_ref().unwrap_or_else(|| {
-                                abort!(field, "Field must have an identifier to be used as an key")
+                for inner in metas {
+                    match inner {
+                        // Check for `#[unistore(key)]` attribute
+                        Meta::Path(p) if p.is_ident("key") => {
+                            if key.is_empty() {
+                                key = field.ty.to_token_stream();
+                                field.ident.as_ref().unwrap_or_else(|| {
+                                    abort!(
+                                        field,
+                                        "Field must have an identifier to be used as an key"
+                                    )
+                                });
+                                key_path = get_field_path(field);
+                            } else {
+                                abort!(
+                                    field.ident,
+                                    "Only one field can be marked with #[unistore(key)]"
+                                );
+                            }
+                        }
+                        // Check for `#[unistore(index)]` attribute
+                        Meta::Path(p) if p.is_ident("index") => {
+                            let name = field.ident.clone().unwrap_or_else(|| {
+                                abort!(
+                                    field,
+                                    "Field must have an identifier to be used as an index"
+                                )
+                            });
+                            let path = get_field_path(field);
+                            let ty = field.ty.to_token_stream();
+                            indices.push(Index {
+                                name,
+                                ty,
+                                path,
+                                normalize: None,
+                                optional: false,
                             });
-                            key_path = get_field_path(field);
-                        } else {
-                            abort!(
-                                field.ident,
-                                "Only one field can be marked with #[unistore(key)]"
-                            );
                         }
+                        // Check for `#[unistore(fulltext)]` attribute
+                        Meta::Path(p) if p.is_ident("fulltext") => {
+                            let name = field.ident.clone().unwrap_or_else(|| {
+                                abort!(
+                                    field,
+                                    "Field must have an identifier to be used as an index"
+                                )
+                            });
+                            let path = quote! { &self.#name };
+                            let ty = field.ty.to_token_stream();
+                            fulltext.push(Index {
+                                name,
+                                ty,
+                                path,
+                                normalize: None,
+                                optional: false,
+                            });
+                        }
+                        // Check for `#[unistore(index(...))]` attribute
+                        Meta::List(list) if list.path.is_ident("index") => {
+                            let name = field.ident.clone().unwrap_or_else(|| {
+                                abort!(
+                                    field,
+                                    "Field must have an identifier to be used as an index"
+                                )
+                            });
+                            let options = IndexOptions::parse(&list);
+                            if options.name.is_some() || options.with.is_some() {
+                                abort!(
+                                    list,
+                                    "`name` and `with` are only supported for indices on the struct"
+                                );
+                            }
+                            let path = get_field_path(field);
+                            let ty = field.ty.to_token_stream();
+                            indices.push(Index {
+                                name,
+                                ty,
+                                path,
+                                normalize: options.normalize,
+                                optional: false,
+                            });
+                        }
+                        // Check for `#[unistore(references = ...)]` attribute
+                        Meta::NameValue(nv) if nv.path.is_ident("references") => {
+                            parent = Some(nv.value.to_token_stream());
+                        }
+                        // Check for `#[unistore(on_delete = "...")]` attribute
+                        Meta::NameValue(nv) if nv.path.is_ident("on_delete") => {
+                            let syn::Expr::Lit(syn::ExprLit {
+                                lit: syn::Lit::Str(policy),
+                                ..
+                            }) = &nv.value
+                            else {
+                                abort!(nv.value, "Expected a string literal");
+                            };
+                            on_delete = Some(match policy.value().as_str() {
+                                "cascade" => OnDelete::Cascade,
+                                "restrict" => OnDelete::Restrict,
+                                "set_null" => OnDelete::SetNull,
+                                other => abort!(policy, "Unknown on_delete policy `{}`", other),
+                            });
+                        }
+                        // Check for `#[unistore(related_name = "...")]` attribute
+                        Meta::NameValue(nv) if nv.path.is_ident("related_name") => {
+                            let syn::Expr::Lit(syn::ExprLit {
+                                lit: syn::Lit::Str(name),
+                                ..
+                            }) = &nv.value
+                            else {
+                                abort!(nv.value, "Expected a string literal");
+                            };
+                            related_name =
+                                Some(name.parse::<Ident>().unwrap_or_else(|e| abort!(name, e)));
+                        }
+                        _ => emit_warning!(attr, "Unsupported unistore attribute"),
                     }
-                    // Check for `#[unistore(index)]` attribute
-                    Meta::Path(p) if p.is_ident("index") => {
-                        let name = field.ident.clone().unwrap_or_else(|| {
-                            abort!(
-                                field,
-                                "Field must have an identifier to be used as an index"
-                            )
-                        });
-                        let path = get_field_path(field);
-                        let ty = field.ty.to_token_stream();
-                        indices.push(Index { name, ty, path });
-                    }
-                    _ => emit_warning!(attr, "Unsupported unistore attribute"),
                 }
             }
+            if let Some(parent) = parent {
+                let field_ident = field.ident.clone().unwrap_or_else(|| {
+                    abort!(field, "Field must have an identifier to reference an item")
+                });
+                let on_delete = on_delete.unwrap_or(OnDelete::Restrict);
+                let inner = option_inner(&field.ty);
+                if on_delete == OnDelete::SetNull && inner.is_none() {
+                    abort!(
+                        field.ty,
+                        "on_delete = \"set_null\" requires the field to be an Option"
+                    );
+                }
+                let (ty, path) = match inner {
+                    Some(inner) => (
+                        inner.to_token_stream(),
+                        quote! {
+                            self.#field_ident.as_ref().map(|value| {
+                                use unistore::{AsKey, Key};
+                                AsKey::<#inner>::as_key(value.clone()).to_key_string()
+                            })
+                        },
+                    ),
+                    None => {
+                        let ty = &field.ty;
+                        (
+                            ty.to_token_stream(),
+                            quote! {
+                                {
+                                    use unistore::{AsKey, Key};
+                                    AsKey::<#ty>::as_key(self.#field_ident.clone()).to_key_string()
+                                }
+                            },
+                        )
+                    }
+                };
+                indices.push(Index {
+                    name: field_ident.clone(),
+                    ty,
+                    path,
+                    normalize: None,
+                    optional: inner.is_some(),
+                });
+                references.push(Reference {
+                    field: field_ident,
+                    parent,
+                    on_delete,
+                    optional: inner.is_some(),
+                    related_name,
+                });
+            } else if on_delete.is_some() || related_name.is_some() {
+                abort!(
+                    field,
+                    "on_delete and related_name require #[unistore(references = ...)]"
+                );
+            }
         }
         if store.is_empty() {
             abort!(
                 input.ident,
-                "Expected #[unistore(store = ...)] attribute on the struct"
+                "Expected #[unistore(store = ...)] or #[unistore(temporary_store = ...)] attribute on the struct"
             )
         }
         if key.is_empty() {
@@ -118,13 +322,85 @@ impl StructArgs {
         }
         StructArgs {
             get_store: store,
+            per_store,
             key,
             key_path,
             indices,
+            fulltext,
+            references,
+            query,
         }
     }
 }
 
+/// Returns `T` if the type is an `Option<T>`.
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(tp) = ty else {
+        return None;
+    };
+    let segment = tp.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct IndexOptions {
+    name: Option<Ident>,
+    with: Option<TokenStream>,
+    ty: Option<TokenStream>,
+    normalize: Option<TokenStream>,
+}
+impl IndexOptions {
+    fn parse(list: &syn::MetaList) -> Self {
+        let mut options = IndexOptions::default();
+        list.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let name: syn::LitStr = meta.value()?.parse()?;
+                options.name = Some(name.parse()?);
+            } else if meta.path.is_ident("with") {
+                let with: syn::Expr = meta.value()?.parse()?;
+                options.with = Some(with.to_token_stream());
+            } else if meta.path.is_ident("ty") {
+                let ty: syn::Type = meta.value()?.parse()?;
+                options.ty = Some(ty.to_token_stream());
+            } else if meta.path.is_ident("normalize") {
+                let value: syn::Expr = meta.value()?.parse()?;
+                options.normalize = Some(parse_normalizer(&value));
+            } else {
+                return Err(meta.error("Unsupported index option"));
+            }
+            Ok(())
+        })
+        .unwrap_or_else(|e| abort!(list, e));
+        options
+    }
+}
+
+/// Maps the names of the built-in normalizers to their functions,
+/// any other expression is used as a user supplied `fn(&str) -> String`.
+fn parse_normalizer(value: &syn::Expr) -> TokenStream {
+    let syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(s),
+        ..
+    }) = value
+    else {
+        return value.to_token_stream();
+    };
+    match s.value().as_str() {
+        "lowercase" => quote! { unistore::normalize::lowercase },
+        "case_fold" | "casefold" => quote! { unistore::normalize::case_fold },
+        other => abort!(s, "Unknown normalizer `{}`", other),
+    }
+}
+
 fn get_field_path(field: &syn::Field) -> TokenStream {
     let field_ident = field.ident.as_ref().unwrap_or_else(|| {
         abort!(
@@ -177,9 +453,13 @@ pub fn derive_unistore_item(input: proc_macro::TokenStream) -> proc_macro::Token
     let name = snake_case(&struc.to_string());
     let StructArgs {
         get_store,
+        per_store,
         key,
         key_path,
         indices,
+        fulltext,
+        references,
+        query,
     } = StructArgs::from_attrs(&input);
 
     let key_table = impl_table(
@@ -187,11 +467,18 @@ pub fn derive_unistore_item(input: proc_macro::TokenStream) -> proc_macro::Token
         &struc.to_token_stream(),
         &name.to_token_stream(),
         &get_store,
+        per_store,
     );
 
     let index_tables = indices.iter().map(|index| {
         let name = snake_case(&index.name.to_string()).to_token_stream();
-        let table = impl_index(&name, &key, &struc.to_token_stream());
+        let table = impl_index(
+            &name,
+            &key,
+            &struc.to_token_stream(),
+            index.normalize.as_ref(),
+            per_store,
+        );
         quote! {
             #name => {
                 #table
@@ -215,41 +502,187 @@ pub fn derive_unistore_item(input: proc_macro::TokenStream) -> proc_macro::Token
         }
     };
 
-    let insert_indices = if indices.is_empty() {
+    let fulltext_tables = fulltext.iter().map(|index| {
+        let name = snake_case(&index.name.to_string()).to_token_stream();
+        let table = impl_fulltext_index(&name, &key, &struc.to_token_stream(), per_store);
+        quote! {
+            #name => {
+                #table
+            }
+        }
+    });
+
+    let get_fulltext = if fulltext.is_empty() {
+        TokenStream::new()
+    } else {
+        quote! {
+            async fn fulltext_index(
+                index: &'static str,
+            ) -> Result<&'static unistore::UniFullTextIndex<'static, Self::Key, Self>, unistore::Error>
+            {
+                match index {
+                    #(#fulltext_tables)*
+                    _ => Err(unistore::Error::MissingIndex(index)),
+                }
+            }
+        }
+    };
+
+    let insert_indices = if indices.is_empty() && fulltext.is_empty() {
         quote! {}
     } else {
         let insertions = indices.iter().map(|index| {
+            let name = snake_case(&index.name.to_string()).to_token_stream();
+            let path = &index.path;
+            if index.optional {
+                quote! {
+                    let index_table = Self::index_table(#name).await?;
+                    match #path {
+                        Some(value) => index_table.insert(value, self.unistore_key()).await?,
+                        None => index_table.remove(self.unistore_key()).await?,
+                    }
+                }
+            } else {
+                quote! {
+                    let index_table = Self::index_table(#name).await?;
+                    index_table.insert(#path, self.unistore_key()).await?;
+                }
+            }
+        });
+        let fulltext_insertions = fulltext.iter().map(|index| {
             let name = snake_case(&index.name.to_string()).to_token_stream();
             let path = &index.path;
             quote! {
-                let index_table = Self::index_table(#name).await?;
+                let index_table = Self::fulltext_index(#name).await?;
                 index_table.insert(#path, self.unistore_key()).await?;
             }
         });
         quote! {
             async fn insert_indices(&self) -> Result<(), unistore::Error> {
                 #(#insertions)*
+                #(#fulltext_insertions)*
                 Ok(())
             }
         }
     };
 
+    let remove_indices = if indices.is_empty() && fulltext.is_empty() {
+        quote! {}
+    } else {
+        let removals = indices.iter().map(|index| {
+            let name = snake_case(&index.name.to_string()).to_token_stream();
+            quote! {
+                let index_table = Self::index_table(#name).await?;
+                index_table.remove(key.clone()).await?;
+            }
+        });
+        let fulltext_removals = fulltext.iter().map(|index| {
+            let name = snake_case(&index.name.to_string()).to_token_stream();
+            quote! {
+                let index_table = Self::fulltext_index(#name).await?;
+                index_table.remove(key.clone()).await?;
+            }
+        });
+        quote! {
+            async fn remove_indices(key: &Self::Key) -> Result<(), unistore::Error> {
+                #(#removals)*
+                #(#fulltext_removals)*
+                Ok(())
+            }
+        }
+    };
+
+    let check_references = if references.is_empty() {
+        quote! {}
+    } else {
+        let checks = references.iter().map(|reference| {
+            let field = &reference.field;
+            let parent = &reference.parent;
+            let check = quote! {
+                use unistore::{AsKey, Key, UniStoreItem};
+                let parent_key =
+                    AsKey::<<#parent as UniStoreItem>::Key>::as_key(parent_key.clone());
+                if !<#parent as UniStoreItem>::contains(parent_key.clone()).await? {
+                    return Err(unistore::Error::ForeignKeyViolation(format!(
+                        "{}.{} references missing {} {}",
+                        stringify!(#struc),
+                        stringify!(#field),
+                        stringify!(#parent),
+                        parent_key.to_key_string(),
+                    )));
+                }
+            };
+            if reference.optional {
+                quote! {
+                    if let Some(parent_key) = &self.#field {
+                        #check
+                    }
+                }
+            } else {
+                quote! {
+                    {
+                        let parent_key = &self.#field;
+                        #check
+                    }
+                }
+            }
+        });
+        quote! {
+            async fn check_references(&self) -> Result<(), unistore::Error> {
+                #(#checks)*
+                Ok(())
+            }
+        }
+    };
+
+    let relations = references
+        .iter()
+        .map(|reference| impl_relation(reference, &struc.to_token_stream(), &name));
+
+    let loaders = impl_loaders(&input, &references, &key);
+
+    let query = match query {
+        true => impl_query(&input, &key, &indices),
+        false => TokenStream::new(),
+    };
+
+    let fulltext_search = fulltext.iter().map(|index| {
+        let name = snake_case(&index.name.to_string()).to_token_stream();
+        let fn_name = format_ident!("search_by_{}", index.name);
+        quote! {
+            pub async fn #fn_name(query: &str) -> Result<Vec<(#key, Self)>, unistore::Error> {
+                use unistore::UniStoreItem;
+                Self::search(#name, query).await
+            }
+        }
+    });
+
     let index_getters = indices.iter().map(|index| {
         let name = snake_case(&index.name.to_string()).to_token_stream();
         let fn_name = format_ident!("get_by_{}", index.name);
         let fn_name_first = format_ident!("get_first_by_{}", index.name);
+        let fn_name_count = format_ident!("count_by_{}", index.name);
+        let fn_name_remove = format_ident!("remove_by_{}", index.name);
         let idx_type = &index.ty;
         quote! {
             pub async fn #fn_name(key: #idx_type) -> Result<Vec<(#key, Self)>, unistore::Error> {
-                use ::unistore::{AsKey, Key};
+                use unistore::{AsKey, Key};
                 let index_table = Self::index_table(#name).await?;
                 index_table.get(key.as_key().to_key_string()).await
             }
             pub async fn #fn_name_first(key: #idx_type) -> Result<Option<(#key, Self)>, unistore::Error> {
-                use ::unistore::{AsKey, Key};
+                use unistore::{AsKey, Key};
                 let index_table = Self::index_table(#name).await?;
                 index_table.get_first(key.as_key().to_key_string()).await
             }
+            pub async fn #fn_name_count(key: #idx_type) -> Result<usize, unistore::Error> {
+                use unistore::{AsKey, Key, UniStoreItem};
+                Self::count_by_index(#name, key.as_key().to_key_string()).await
+            }
+            pub async fn #fn_name_remove(key: #idx_type) -> Result<usize, unistore::Error> {
+                use unistore::{AsKey, Key, UniStoreItem};
+                Self::remove_by_index(#name, key.as_key().to_key_string()).await
+            }
         }
     });
 
@@ -263,8 +696,14 @@ pub fn derive_unistore_item(input: proc_macro::TokenStream) -> proc_macro::Token
 
             #get_index
 
+            #get_fulltext
+
             #insert_indices
 
+            #remove_indices
+
+            #check_references
+
             fn unistore_key(&self) -> Self::Key {
                 #key_path
             }
@@ -272,52 +711,465 @@ pub fn derive_unistore_item(input: proc_macro::TokenStream) -> proc_macro::Token
 
         impl #struc{
             #(#index_getters)*
+            #(#fulltext_search)*
         }
+
+        #(#relations)*
+
+        #loaders
+
+        #query
     };
     proc_macro::TokenStream::from(expanded)
 }
 
+/// Creates a value once with `init` after running `setup`, and caches it in a `OnceCache`.
+/// For items with `temporary_store` the values are cached per `owner` instead,
+/// as every thread gets its own store.
+fn impl_cached(
+    ty: TokenStream,
+    setup: TokenStream,
+    owner: TokenStream,
+    init: TokenStream,
+    per_store: bool,
+) -> TokenStream {
+    if per_store {
+        return quote! {
+            let cache = unistore::static_ref!(
+                unistore::StoreCache<#ty>,
+                unistore::StoreCache::new()
+            );
+            #setup
+            cache.get_or_init(#owner, || async { #init }).await
+        };
+    }
+    quote! {
+        let cache = unistore::static_ref!(unistore::OnceCache<#ty>, unistore::OnceCache::new());
+        cache.get_or_init(|| async { #setup #init }).await
+    }
+}
+
 fn impl_table(
     key: &TokenStream,
     val: &TokenStream,
     name: &TokenStream,
     get_store: &TokenStream,
+    per_store: bool,
+) -> TokenStream {
+    impl_cached(
+        quote! { unistore::UniTable<'static, #key, #val> },
+        quote! { let store = #get_store().await; },
+        quote! { store },
+        quote! { store.create_table(#name, true).await.expect("Failed to create table") },
+        per_store,
+    )
+}
+
+fn impl_index(
+    name: &TokenStream,
+    key: &TokenStream,
+    val: &TokenStream,
+    normalize: Option<&TokenStream>,
+    per_store: bool,
 ) -> TokenStream {
+    let create_index = match normalize {
+        Some(normalize) => quote! { table.create_index_normalized(#name, #normalize) },
+        None => quote! { table.create_index(#name) },
+    };
+    let index = impl_cached(
+        quote! { unistore::UniIndex<'static, String, #key, #val> },
+        quote! { let table = Self::table().await; },
+        quote! { table },
+        quote! { #create_index.await.expect("Failed to create index") },
+        per_store,
+    );
+    quote! { Ok({ #index }) }
+}
+
+fn impl_fulltext_index(
+    name: &TokenStream,
+    key: &TokenStream,
+    val: &TokenStream,
+    per_store: bool,
+) -> TokenStream {
+    let index = impl_cached(
+        quote! { unistore::UniFullTextIndex<'static, #key, #val> },
+        quote! { let table = Self::table().await; },
+        quote! { table },
+        quote! { table.create_fulltext_index(#name).await.expect("Failed to create index") },
+        per_store,
+    );
+    quote! { Ok({ #index }) }
+}
+
+fn impl_relation(reference: &Reference, child: &TokenStream, child_name: &str) -> TokenStream {
+    let field = &reference.field;
+    let parent = &reference.parent;
+    let index = snake_case(&field.to_string()).to_token_stream();
+    let restrict = quote! {
+        let count = <#child as UniStoreItem>::count_by_index(#index, key.as_str()).await?;
+        if count > 0 {
+            return Err(unistore::Error::ForeignKeyViolation(format!(
+                "{} {} is still referenced by {} {} items",
+                stringify!(#parent),
+                key,
+                count,
+                stringify!(#child),
+            )));
+        }
+        Ok(())
+    };
+    let (policy, check, apply) = match reference.on_delete {
+        OnDelete::Cascade => (
+            quote! { Cascade },
+            quote! {
+                let index = <#child as UniStoreItem>::index_table(#index).await?;
+                for child_key in index.get_keys(key.as_str()).await? {
+                    <#child as UniStoreItem>::check_remove(child_key).await?;
+                }
+                Ok(())
+            },
+            quote! {
+                <#child as UniStoreItem>::remove_by_index(#index, key).await?;
+                Ok(())
+            },
+        ),
+        OnDelete::Restrict => (quote! { Restrict }, restrict.clone(), restrict),
+        OnDelete::SetNull => (
+            quote! { SetNull },
+            quote! {
+                let _ = key;
+                Ok(())
+            },
+            quote! {
+                let children =
+                    <#child as UniStoreItem>::get_by_index::<String>(#index, key.as_str()).await?;
+                for (_, mut child) in children {
+                    child.#field = None;
+                    child.save().await?;
+                }
+                Ok(())
+            },
+        ),
+    };
+    quote! {
+        const _: () = {
+            use unistore::UniStoreItem;
+
+            fn parent() -> std::any::TypeId {
+                std::any::TypeId::of::<#parent>()
+            }
+
+            fn check(key: String) -> unistore::RelationFuture {
+                Box::pin(async move { #check })
+            }
+
+            fn apply(key: String) -> unistore::RelationFuture {
+                Box::pin(async move { #apply })
+            }
+
+            unistore::inventory::submit! {
+                unistore::Relation {
+                    parent,
+                    child: #child_name,
+                    on_delete: unistore::OnDelete::#policy,
+                    check,
+                    apply,
+                }
+            }
+        };
+    }
+}
+
+/// Generates `child.parent()` navigation methods and `parent.children()` loaders, together with
+/// their batched `load_*` variants. The loaders are defined by a `{Child}Loaders` trait that is
+/// implemented for the referenced type, or one `{Child}{Parent}Loaders` trait per referenced type
+/// if the child references several types.
+fn impl_loaders(
+    input: &DeriveInput,
+    references: &[Reference],
+    child_key: &TokenStream,
+) -> TokenStream {
+    let child = &input.ident;
+    let getters = references
+        .iter()
+        .map(|reference| impl_parent_loaders(reference, child));
+
+    // Group the references by the referenced type, keeping their order
+    let mut parents: Vec<(String, Vec<&Reference>)> = Vec::new();
+    for reference in references {
+        let parent = reference.parent.to_string();
+        match parents.iter_mut().find(|(name, _)| *name == parent) {
+            Some((_, group)) => group.push(reference),
+            None => parents.push((parent, vec![reference])),
+        }
+    }
+    let single = parents.len() == 1;
+    let traits = parents.iter().map(|(_, group)| {
+        let trait_name = if single {
+            format_ident!("{}Loaders", child)
+        } else {
+            let parent = syn::parse2::<syn::Path>(group[0].parent.clone()).unwrap_or_else(|_| {
+                abort!(group[0].parent, "Expected a path to the referenced type")
+            });
+            let parent = &parent
+                .segments
+                .last()
+                .expect("Path should not be empty")
+                .ident;
+            format_ident!("{}{}Loaders", child, parent)
+        };
+        impl_children_loaders(input, &trait_name, group, child_key)
+    });
+
+    quote! {
+        impl #child {
+            #(#getters)*
+        }
+
+        #(#traits)*
+    }
+}
+
+/// Generates the methods of the child that load the item referenced by `reference`.
+fn impl_parent_loaders(reference: &Reference, child: &Ident) -> TokenStream {
+    let field = &reference.field;
+    let parent = &reference.parent;
+    let field_name = field.to_string();
+    let getter = format_ident!("{}", field_name.strip_suffix("_id").unwrap_or(&field_name));
+    let load_getter = format_ident!("load_{}", getter);
+
+    let (parent_key, parent_keys) = if reference.optional {
+        (
+            quote! {
+                let Some(parent_key) = self.#field.clone() else {
+                    return Ok(None);
+                };
+            },
+            quote! { items.iter().map(|item| item.#field.clone()).collect::<Vec<_>>() },
+        )
+    } else {
+        (
+            quote! { let parent_key = self.#field.clone(); },
+            quote! { items.iter().map(|item| Some(item.#field.clone())).collect::<Vec<_>>() },
+        )
+    };
+
     quote! {
-        static TABLE: std::sync::OnceLock<unistore::UniTable<'static, #key, #val>> =
-            std::sync::OnceLock::new();
-        static INITIALIZING: unistore::Mutex<()> = unistore::Mutex::new(());
+        /// Loads the item referenced by this item.
+        pub async fn #getter(&self) -> Result<Option<#parent>, unistore::Error> {
+            use unistore::UniStoreItem;
+            #parent_key
+            <#parent as UniStoreItem>::get(parent_key).await
+        }
 
-        if let Some(table) = TABLE.get() {
-            return table;
+        /// Loads the referenced items of multiple items in one batch.
+        /// The result has one entry per item, in the same order.
+        pub async fn #load_getter(items: &[#child]) -> Result<Vec<Option<#parent>>, unistore::Error> {
+            use unistore::UniStoreItem;
+            let parent_keys = #parent_keys;
+            let table = <#parent as UniStoreItem>::table().await;
+            let mut parents = table
+                .get_many(parent_keys.iter().flatten().cloned())
+                .await?
+                .into_iter();
+            Ok(parent_keys
+                .into_iter()
+                .map(|key| key.and_then(|_| parents.next().flatten()))
+                .collect())
         }
-        let _lock = INITIALIZING.lock().await;
-        if let Some(table) = TABLE.get() {
-            return table;
+    }
+}
+
+/// Generates the extension trait with the loaders of the children referencing one type,
+/// as inherent methods can't be added to a type of another crate.
+fn impl_children_loaders(
+    input: &DeriveInput,
+    trait_name: &Ident,
+    references: &[&Reference],
+    child_key: &TokenStream,
+) -> TokenStream {
+    let child = &input.ident;
+    let vis = &input.vis;
+    let parent = &references[0].parent;
+    let mut names = Vec::new();
+    let mut declarations = Vec::new();
+    let mut methods = Vec::new();
+    for reference in references {
+        let index = snake_case(&reference.field.to_string()).to_token_stream();
+        let children = reference
+            .related_name
+            .clone()
+            .unwrap_or_else(|| format_ident!("{}", plural(&snake_case(&child.to_string()))));
+        if names.contains(&children) {
+            abort!(
+                reference.field,
+                "Another reference to {} already generates the loader `{}`, set a different #[unistore(related_name = \"...\")]",
+                parent,
+                children
+            );
+        }
+        let load_children = format_ident!("load_{}", children);
+        let doc = format!(
+            "Loads all [`{child}`] items referencing this item through `{}`.",
+            reference.field
+        );
+        declarations.push(quote! {
+            #[doc = #doc]
+            fn #children(&self) -> impl std::future::Future<Output = Result<Vec<#child>, unistore::Error>>;
+
+            /// Loads the referencing items of multiple items in one batch.
+            /// The result has one entry per item, in the same order.
+            fn #load_children(
+                items: &[Self],
+            ) -> impl std::future::Future<Output = Result<Vec<Vec<#child>>, unistore::Error>>;
+        });
+        methods.push(quote! {
+            fn #children(&self) -> impl std::future::Future<Output = Result<Vec<#child>, unistore::Error>> {
+                use unistore::{Key, UniStoreItem};
+                async move {
+                    let children = <#child as UniStoreItem>::get_by_index::<String>(
+                        #index,
+                        self.unistore_key().to_key_string(),
+                    )
+                    .await?;
+                    Ok(children.into_iter().map(|(_, child)| child).collect())
+                }
+            }
+
+            fn #load_children(
+                items: &[Self],
+            ) -> impl std::future::Future<Output = Result<Vec<Vec<#child>>, unistore::Error>> {
+                use unistore::{Key, UniStoreItem};
+                async move {
+                    let index = <#child as UniStoreItem>::index_table(#index).await?;
+                    let children = index
+                        .get_many(items.iter().map(|item| item.unistore_key().to_key_string()))
+                        .await?;
+                    Ok(children
+                        .into_iter()
+                        .map(|children| children.into_iter().map(|(_, child): (#child_key, #child)| child).collect())
+                        .collect())
+                }
+            }
+        });
+        names.push(children);
+    }
+    let doc = format!("Loaders for the [`{child}`] items referencing an item.");
+    quote! {
+        #[doc = #doc]
+        #vis trait #trait_name: Sized {
+            #(#declarations)*
+        }
+
+        impl #trait_name for #parent {
+            #(#methods)*
         }
-        let store = #get_store().await;
-        let table = store.create_table(#name, true).await.expect("Failed to create table");
-        TABLE.set(table).expect("Failed to set table");
-        TABLE.get().unwrap()
     }
 }
 
-fn impl_index(name: &TokenStream, key: &TokenStream, val: &TokenStream) -> TokenStream {
+/// Returns the English plural of a snake case name.
+/// Irregular plurals have to be set with `related_name`.
+fn plural(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix('y')
+        && !stem.ends_with(['a', 'e', 'i', 'o', 'u'])
+    {
+        return format!("{stem}ies");
+    }
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+    {
+        return format!("{name}es");
+    }
+    format!("{name}s")
+}
+
+/// Methods of the generated query type that can't be used as field filters.
+const QUERY_METHODS: [&str; 4] = ["filter", "offset", "limit", "execute"];
+
+/// Generates a `<Struct>Query` type with one filter method per field
+/// and a `query()` constructor on the struct, for structs with `#[unistore(query)]`.
+fn impl_query(input: &DeriveInput, key: &TokenStream, indices: &[Index]) -> TokenStream {
+    let struc = &input.ident;
+    let vis = &input.vis;
+    let query = format_ident!("{}Query", struc);
+    let Data::Struct(data) = &input.data else {
+        unreachable!("checked in StructArgs::from_attrs");
+    };
+    let fields = data.fields.iter().filter_map(|field| {
+        let ident = field.ident.as_ref()?;
+        if QUERY_METHODS.contains(&ident.to_string().as_str()) {
+            abort!(
+                ident,
+                "The field `{}` can't be queried with #[unistore(query)], as the query type has a method with the same name",
+                ident
+            );
+        }
+        let ty = &field.ty;
+        let indexed = indices
+            .iter()
+            .any(|index| &index.name == ident && !index.optional);
+        let index = if indexed {
+            let name = snake_case(&ident.to_string());
+            quote! {
+                Some((#name, |value: &#ty| {
+                    use unistore::{AsKey, Key};
+                    AsKey::<#ty>::as_key(value.clone()).to_key_string()
+                }))
+            }
+        } else {
+            quote! { None }
+        };
+        Some(quote! {
+            pub fn #ident(self) -> unistore::FieldFilter<Self, #ty> {
+                unistore::FieldFilter::new(self, |item: &#struc| &item.#ident, #index)
+            }
+        })
+    });
     quote! {
-        static INDEX: std::sync::OnceLock<unistore::UniIndex<'static, String, #key, #val>> =
-            std::sync::OnceLock::new();
-        static INITIALIZING: unistore::Mutex<()> = unistore::Mutex::new(());
+        /// Typed query on the fields of the item, created by the `query` method.
+        #[derive(Debug, Default)]
+        #vis struct #query(unistore::ItemQuery<#struc>);
+
+        impl unistore::TypedQuery for #query {
+            type Item = #struc;
 
-        if let Some(index) = INDEX.get() {
-            return Ok(index);
+            fn item_query(&mut self) -> &mut unistore::ItemQuery<#struc> {
+                &mut self.0
+            }
         }
-        let _lock = INITIALIZING.lock().await;
-        if let Some(index) = INDEX.get() {
-            return Ok(index);
+
+        impl #query {
+            #(#fields)*
+
+            /// Keeps items for which `filter` returns `true`.
+            pub fn filter(mut self, filter: impl Fn(&#struc) -> bool + 'static) -> Self {
+                self.0.filter(filter);
+                self
+            }
+
+            /// Skips the first `offset` results.
+            pub fn offset(mut self, offset: usize) -> Self {
+                self.0.offset(offset);
+                self
+            }
+
+            /// Returns at most `limit` results.
+            pub fn limit(mut self, limit: usize) -> Self {
+                self.0.limit(limit);
+                self
+            }
+
+            pub async fn execute(self) -> Result<Vec<(#key, #struc)>, unistore::Error> {
+                self.0.execute().await
+            }
+        }
+
+        impl #struc {
+            pub fn query() -> #query {
+                #query::default()
+            }
         }
-        let table = Self::table().await;
-        let index = table.create_index(#name).await.expect("Failed to create index");
-        INDEX.set(index).expect("Failed to set table");
-        Ok(INDEX.get().unwrap())
     }
 }